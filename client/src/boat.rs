@@ -1,20 +1,56 @@
 use bevy::{color::palettes::css::GRAY, prelude::*};
 use common::{
     Boat, BoatReverseNegative, BoatReversePositive, BoatType, CIRCLE_HUD, CircleHud, OCEAN_SURFACE,
-    circle_hud_mesh,
+    Sinking, Turrets, circle_hud_mesh,
+    definitions::{self, Definitions},
     primitives::{CustomTransform, MeshBundle, Size, WeaponCounter},
+    protocol::Nickname,
     util::OrderedHashMap,
 };
 use lightyear::prelude::*;
 
 use crate::asset::SpriteMap;
+use crate::ui::UpdateWeaponSelectionBar;
 
 pub(crate) struct BoatPlugin;
 
 impl Plugin for BoatPlugin {
     fn build(&self, app: &mut App) {
         app.add_observer(spawn_boat)
-            .add_systems(FixedUpdate, sync_transform_from_custom);
+            .add_observer(on_sinking)
+            .add_observer(spawn_nickname_label)
+            .add_systems(Update, follow_boat)
+            .add_systems(FixedUpdate, sync_transform_from_custom)
+            .add_systems(Update, (spawn_turret_barrels, sync_turret_barrels).chain());
+
+        // messages MUST be Update or PreUpdate .after(Receive)
+        app.add_systems(Update, recv_definitions);
+    }
+}
+
+/// applies the server's stat overrides
+///
+/// sent before our boat is spawned, but replication may still win the race
+fn recv_definitions(
+    mut rx: Single<&mut MessageReceiver<Definitions>>,
+    mut boat: Query<(&Boat, &mut WeaponCounter), With<Controlled>>,
+    mut commands: Commands,
+) {
+    for received in rx.receive() {
+        info!("Received definitions (version {})", received.version);
+        // rejoining the same server sends the same definitions again
+        if let Err(received) = definitions::set_active(received)
+            && definitions::active() != Some(&received)
+        {
+            warn!("Received different definitions, restart to apply them");
+            continue;
+        }
+
+        if let Ok((&boat, mut counter)) = boat.single_mut() {
+            warn!("Definitions arrived after our boat spawned, refilling weapons");
+            *counter = WeaponCounter::from_boat(&boat);
+            commands.trigger(UpdateWeaponSelectionBar { target: boat });
+        }
     }
 }
 
@@ -133,6 +169,127 @@ fn spawn_boat(
     commands.insert_resource(BoatType(boat.sub_kind()));
 }
 
+/// barrel drawn on top of the hull for each turret, there are no turret sprites
+#[derive(Debug, Component)]
+struct TurretBarrel {
+    /// into [`Boat::mounts`]
+    index: usize,
+}
+
+/// pixels
+const BARREL_SIZE: Vec2 = vec2(14.0, 3.0);
+/// just above the hull
+const TURRET_Z: f32 = 0.01;
+
+/// (re)spawns barrels when a boat first gets [`Turrets`] or is upgraded
+fn spawn_turret_barrels(
+    boats: Query<(&Boat, Entity), (With<Turrets>, Or<(Added<Turrets>, Changed<Boat>)>)>,
+    barrels: Query<(&ChildOf, Entity), With<TurretBarrel>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (boat, boat_id) in boats {
+        for (_, barrel) in barrels.iter().filter(|(child_of, _)| child_of.parent() == boat_id) {
+            commands.entity(barrel).despawn();
+        }
+        let mesh = meshes.add(Rectangle::from_size(BARREL_SIZE));
+        let material = materials.add(ColorMaterial::from_color(GRAY));
+
+        commands.entity(boat_id).with_children(|parent| {
+            for (index, mount) in boat.mounts().iter().enumerate().filter(|(_, mount)| !mount.is_fixed()) {
+                parent.spawn((
+                    MeshBundle {
+                        mesh: Mesh2d(mesh.clone()),
+                        materials: MeshMaterial2d(material.clone()),
+                    },
+                    Transform::from_translation((mount.position * Boat::SIZE_TO_RENDER_MULTIPLIER).extend(TURRET_Z)),
+                    TurretBarrel { index },
+                ));
+            }
+        });
+    }
+}
+
+/// barrels are children of the hull, so only the azimuth relative to the bow is applied
+fn sync_turret_barrels(
+    boats: Query<(&Turrets, &Boat)>,
+    barrels: Query<(&mut Transform, &TurretBarrel, &ChildOf)>,
+) {
+    for (mut transform, barrel, child_of) in barrels {
+        let Ok((turrets, boat)) = boats.get(child_of.parent()) else {
+            continue;
+        };
+        let (Some(mount), Some(&azimuth)) = (boat.mounts().get(barrel.index), turrets.0.get(barrel.index)) else {
+            continue;
+        };
+        // the barrel sticks out from the centre of the turret
+        let mount_position = mount.position * Boat::SIZE_TO_RENDER_MULTIPLIER;
+        transform.translation = (mount_position + azimuth.to_vec() * BARREL_SIZE.x / 2.0).extend(TURRET_Z);
+        transform.rotation = azimuth.to_quat();
+    }
+}
+
+/// darkens boats that started sinking, the server despawns them shortly after
+fn on_sinking(trigger: On<Add, Sinking>, mut sprites: Query<&mut Sprite, With<Boat>>) {
+    if let Ok(mut sprite) = sprites.get_mut(trigger.entity) {
+        sprite.color = Color::srgba(0.3, 0.3, 0.3, 0.6);
+    }
+}
+
+/// others' [`Nickname`] under their sprite, a separate entity so it doesn't rotate with the hull
+#[derive(Debug, Component)]
+struct NicknameLabel {
+    boat: Entity,
+    /// pixels below the boat's centre, clear of the sprite whatever its rotation
+    offset: f32,
+}
+
+const NICKNAME_FONT_SIZE: FontSize = FontSize::Px(14.0);
+/// pixels between the sprite and the label
+const NICKNAME_MARGIN: f32 = 6.0;
+
+fn spawn_nickname_label(
+    trigger: On<Add, Nickname>,
+    boats: Query<(&Nickname, &Boat), Without<Controlled>>,
+    mut commands: Commands,
+) {
+    let Ok((nickname, boat)) = boats.get(trigger.entity) else {
+        return; // ours
+    };
+    commands.spawn((
+        Text2d::new(nickname.0.clone()),
+        TextFont {
+            font: FontSource::SansSerif,
+            font_size: NICKNAME_FONT_SIZE,
+            ..default()
+        },
+        TextColor(Color::WHITE.with_alpha(0.8)),
+        Transform::from_xyz(0.0, 0.0, *CIRCLE_HUD),
+        NicknameLabel {
+            boat: trigger.entity,
+            offset: boat.render_size().max_element() / 2.0 + NICKNAME_MARGIN,
+        },
+        Name::new("Nickname label"),
+    ));
+}
+
+/// despawns the label along with its boat
+fn follow_boat(
+    labels: Query<(&mut Transform, &NicknameLabel, Entity)>,
+    boats: Query<&Transform, (With<Boat>, Without<NicknameLabel>)>,
+    mut commands: Commands,
+) {
+    for (mut transform, label, id) in labels {
+        let Ok(boat) = boats.get(label.boat) else {
+            commands.entity(id).despawn();
+            continue;
+        };
+        transform.translation.x = boat.translation.x;
+        transform.translation.y = boat.translation.y - label.offset;
+    }
+}
+
 // is it better to directly manipulate Transform
 /// for all boats regardless of control
 fn sync_transform_from_custom(
@@ -3,11 +3,9 @@ use bevy::prelude::*;
 use bevy::render::render_resource::AsBindGroup;
 use bevy::shader::ShaderRef;
 use bevy::sprite_render::AlphaMode2d;
-use common::primitives::{
-    Altitude as _, CustomTransform, DecimalPoint, GetZIndex, MaybePushToSurface, MeshBundle, ZIndex,
-};
-use common::protocol::{EntityOnServer, ZIndexUpdate};
-use common::util::{calculate_diving_overlay, in_states_2, not_in_state};
+use common::primitives::{CustomTransform, GetZIndex, MeshBundle, ZIndex};
+use common::protocol::DiveIntent;
+use common::util::calculate_diving_overlay;
 use common::{Boat, BoatType, MainCamera, OCEAN_FLOOR, OCEAN_SURFACE, SubKind, eq};
 use lightyear::input::client::InputSystems;
 use lightyear::prelude::Controlled;
@@ -24,23 +22,12 @@ impl Plugin for DivingPlugin {
         app.add_systems(Update, update_diving_overlay);
         app.add_systems(
             FixedUpdate,
-            (
-                update_diving_status
-                    .run_if(input_just_pressed(KeyCode::KeyR))
-                    .run_if(resource_exists_and_equals(BoatType(SubKind::Submarine))),
-                act_on_state.run_if(not_in_state(DivingStatus::None)),
-            )
-                .chain(),
+            update_diving_status
+                .run_if(input_just_pressed(KeyCode::KeyR))
+                .run_if(resource_exists_and_equals(BoatType(SubKind::Submarine))),
         );
-        app.add_systems(
-            FixedPreUpdate,
-            (
-                act_on_state.run_if(in_states_2(DivingStatus::Diving, DivingStatus::Surfacing)),
-                clear_z_update.run_if(in_state(DivingStatus::None)),
-            )
-                .in_set(InputSystems::WriteClientInputs),
-        );
-        app.add_observer(push_to_surface_on_upgrade);
+        // the depth itself is simulated by `DivePlugin` in common
+        app.add_systems(FixedPreUpdate, write_dive_intent.in_set(InputSystems::WriteClientInputs));
         app.add_systems(
             FixedUpdate,
             dbg_just_pressed.run_if(input_just_pressed(KeyCode::KeyR)),
@@ -110,8 +97,6 @@ enum DivingStatus {
     None,
     Surfacing,
     Diving,
-    /// when a submarine upgrades to a surface ship
-    PushingToSurface(Boat), // due to f32 not Eq, can't store Speed
 }
 
 fn update_diving_status(
@@ -130,105 +115,34 @@ fn update_diving_status(
         }
         DivingStatus::Surfacing => DivingStatus::Diving,
         DivingStatus::Diving => DivingStatus::Surfacing,
-        DivingStatus::PushingToSurface(_) => {
-            warn!("Should not run the system");
-            return;
-        }
     };
 
     setter.set(target);
 }
 
-/// modifies `Transform::z` and sends new ZIndex to server
-fn act_on_state(
-    ships: Single<(&mut Transform, &Boat, &mut ZIndex, &EntityOnServer), With<Controlled>>,
+/// only sends which way to go, back to [`DiveIntent::Hold`] once the depth is reached
+fn write_dive_intent(
+    ship: Single<(&ZIndex, &Boat), With<Controlled>>,
     diving_status: Res<State<DivingStatus>>,
     mut setter: ResMut<NextState<DivingStatus>>,
-    // mut tcp_wrapper: ResMut<TcpWrapper>
-    // mut sender: Single<&mut MessageSender<NewZIndex>>,
-    mut z_update: Single<&mut ActionState<ZIndexUpdate>, With<InputMarker<ZIndexUpdate>>>,
-) {
-    let (mut transform, boat, mut z_index, _e) = ships.into_inner();
-
-    match diving_status.get() {
-        DivingStatus::Diving => {
-            // local simulation
-            *z_index = transform.decrease_with_limit(boat.diving_speed().get_raw(), OCEAN_FLOOR);
-
-            if transform.reached(OCEAN_FLOOR, DecimalPoint::Three) {
-                setter.set(DivingStatus::None);
-            }
-        }
-        DivingStatus::Surfacing => {
-            *z_index = transform.increase_with_limit(boat.diving_speed().get_raw(), OCEAN_SURFACE);
-
-            if transform.reached(OCEAN_SURFACE, DecimalPoint::Three) {
-                setter.set(DivingStatus::None);
-            }
-        }
-        DivingStatus::PushingToSurface(target) => {
-            *z_index =
-                transform.increase_with_limit(target.diving_speed().get_raw(), OCEAN_SURFACE);
-
-            if transform.reached(OCEAN_SURFACE, DecimalPoint::Three) {
-                setter.set(DivingStatus::None);
-            }
-        }
-        DivingStatus::None => {
-            warn!("Should only act_on_state if in DivingStatus::Diving or DivingStatus::Surfacing");
-            return;
-        }
-    }
-
-    trace!("State: {:?}, Depth: {:?}", diving_status.get(), z_index);
-    // let amount = tcp_wrapper.write(&TcpClientRequest::NewZIndex(*z_index).to_bytes()).unwrap();
-    // assert_eq!(amount, 4);
-
-    // sender.send::<SendToServerOrdered>(NewZIndex { new_index: **z_index, entity_on_server: *e });
-
-    // info!(?z_index);
-    z_update.0 = ZIndexUpdate(Some(*z_index));
-    // unsafe { FRAME_TO_CLEAR = Some(50); }
-}
-
-#[expect(clippy::partialeq_to_none)]
-fn clear_z_update(
-    mut z_update: Single<&mut ActionState<ZIndexUpdate>, With<InputMarker<ZIndexUpdate>>>,
+    mut intent: Single<&mut ActionState<DiveIntent>, With<InputMarker<DiveIntent>>>,
 ) {
-    if z_update.0.0 == None {
-        return;
-    }
-
-    // ActionState is not accurate therefore uses ?precision = 0.05 in z index comparisons
-    unsafe {
-        if FRAME_TO_CLEAR == None {
-            FRAME_TO_CLEAR = Some(80);
-            return;
+    let (z_index, boat) = ship.into_inner();
+    // upgraded to a surface ship mid-dive, it surfaces on its own
+    let submarine = boat.sub_kind() == SubKind::Submarine;
+
+    let next = match diving_status.get() {
+        DivingStatus::Diving if submarine && *z_index > boat.max_depth() => DiveIntent::Dive,
+        DivingStatus::Surfacing if submarine && *z_index < OCEAN_SURFACE => DiveIntent::Surface,
+        DivingStatus::None => DiveIntent::Hold,
+        _ => {
+            setter.set(DivingStatus::None);
+            DiveIntent::Hold
         }
-        let Some(ref mut f) = FRAME_TO_CLEAR else {
-            unreachable!()
-        };
-
-        *f -= 1;
-
-        if *f == 0 {
-            z_update.0 = ZIndexUpdate(None);
-            debug!("Cleared ZIndexUpdate after 80 frames");
-            FRAME_TO_CLEAR = None;
-        }
-    }
-}
-
-static mut FRAME_TO_CLEAR: Option<u8> = None;
-
-fn push_to_surface_on_upgrade(
-    trigger: On<MaybePushToSurface>,
-    transform: Single<&Transform, With<Controlled>>,
-    mut setter: ResMut<NextState<DivingStatus>>,
-) {
-    if !transform.reached(OCEAN_SURFACE, DecimalPoint::Three) {
-        debug!("Surfacing after upgrading to a ship");
-        setter.set(DivingStatus::PushingToSurface(trigger.last_boat));
+    };
+    if intent.0 != next {
+        trace!("State: {:?}, Depth: {:?}", diving_status.get(), z_index);
+        intent.0 = next;
     }
 }
 
@@ -18,7 +18,7 @@ use std::collections::HashSet;
 use bevy::{input::common_conditions::input_pressed, prelude::*};
 use common::{Boat, eq, in_one_of_states, primitives::{
     CursorPos, CustomTransform, FlipRadian as _, NormalizeRadian as _, Radian, Speed, WrapRadian as _
-}, protocol::{Move, Rotate}, util::{Direction, InputEnabled, KeyboardInputExt, add_circle_hud, calculate_from_proportion, get_rotate_radian, input_not_pressed, not_stopped}};
+}, protocol::{AimTurrets, Move, Rotate}, util::{Direction, InputEnabled, KeyboardInputExt, add_circle_hud, calculate_from_proportion, get_rotate_radian, input_not_pressed, not_stopped}};
 use lightyear::{
     input::client::InputSystems,
     prelude::{
@@ -80,6 +80,9 @@ impl Plugin for InputBufferPlugin {
                 .run_if(|input: Single<&ActionState<Move>, With<InputMarker<Move>>>, custom: Single<&CustomTransform, With<Controlled>>| input.0.0.is_some() && eq!(custom.speed.get_raw(), input.0.0.unwrap().get_raw()))
         ).run_if(input_not_pressed(MouseButton::Left)));  // 
 
+        // turrets follow the cursor whether or not the boat is being steered
+        app.add_systems(FixedPreUpdate, buffer_aim_turrets.in_set(InputSystems::WriteClientInputs));
+
         // clear AfterUpgradeDontClearMoveState
         app.add_systems(FixedPreUpdate, (|q: Single<(&CustomTransform, &Boat), With<Controlled>>, mut move_input: Single<&mut ActionState<Move>, With<InputMarker<Move>>>, mut state: ResMut<NextState<AfterUpgradeDontClearMoveState>>| {
             let (custom, boat) = q.into_inner();
@@ -112,6 +115,16 @@ fn update_keyboard_inputs(
     }
 }
 
+/// buffer the cursor as the point the [`Turrets`](common::Turrets) turn towards
+fn buffer_aim_turrets(
+    cursor_pos: Res<CursorPos>,
+    mut aim: Single<&mut ActionState<AimTurrets>, With<InputMarker<AimTurrets>>>,
+) {
+    if aim.0.0 != Some(cursor_pos.0) {
+        aim.0.0 = Some(cursor_pos.0);
+    }
+}
+
 /// buffer the [`ActionState<Rotate>`] for the target rotation the client wants to go to
 /// i.e. not modifying ActionState outside [here](self)
 fn buffer_rotate(
@@ -0,0 +1,192 @@
+//! asks for a nickname before connecting
+//!
+//! the server validates it on `/join` and answers with a connect token,
+//! or why the nickname was rejected, shown under the field until the next try.
+//! the request runs on the [`IoTaskPool`] so the window keeps drawing while it waits
+//!
+//! the server is `?server=<url>` on the page, `MK73_SERVER_URL` at build time, or the local one
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use bevy::tasks::{IoTaskPool, Task, block_on, futures_lite::future};
+use std::net::SocketAddr;
+
+use common::{GAME_ADDR_HEADER, TCP_ADDR};
+use common::protocol::Nickname;
+use lightyear::netcode::ConnectToken;
+
+pub(crate) struct JoinPlugin;
+
+impl Plugin for JoinPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_join_screen.after(crate::setup))
+            .add_systems(Update, (type_nickname, poll_join).chain().run_if(any_with_component::<JoinScreen>));
+    }
+}
+
+/// despawned once joined
+#[derive(Debug, Component)]
+struct JoinScreen;
+
+/// what the player typed so far
+#[derive(Debug, Component, Default)]
+struct NicknameField(String);
+
+#[derive(Debug, Component)]
+struct JoinError;
+
+/// the pending `/join` request, on the [`JoinScreen`] while it runs
+#[derive(Component)]
+struct JoinTask(Task<Result<(ConnectToken, SocketAddr), String>>);
+
+const JOIN_FONT_SIZE: FontSize = FontSize::Px(24.0);
+const JOIN_HINT: &str = "Enter to join";
+const JOINING: &str = "Joining...";
+const ERROR_COLOR: Color = Color::linear_rgb(1.0, 0.3, 0.3);
+
+fn spawn_join_screen(mut commands: Commands) {
+    let font = TextFont {
+        font: FontSource::SansSerif,
+        font_size: JOIN_FONT_SIZE,
+        ..default()
+    };
+    commands.spawn((
+        Node {
+            width: percent(100),
+            height: percent(100),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: px(10),
+            ..default()
+        },
+        BackgroundColor(Color::linear_rgba(0.0, 0.0, 0.0, 0.5)),
+        JoinScreen,
+        children![
+            (Text::new("Nickname"), font.clone()),
+            (Text::new("_"), font.clone(), NicknameField::default()),
+            (Text::new(JOIN_HINT), font, JoinError),
+        ],
+    ));
+}
+
+fn type_nickname(
+    mut keys: MessageReader<KeyboardInput>,
+    field: Single<(&mut NicknameField, &mut Text), Without<JoinError>>,
+    error: Single<(&mut Text, &mut TextColor), With<JoinError>>,
+    screen: Single<(Entity, Has<JoinTask>), With<JoinScreen>>,
+    mut commands: Commands,
+) {
+    let (mut field, mut field_text) = field.into_inner();
+    let (mut error_text, mut error_color) = error.into_inner();
+    let (screen, mut joining) = screen.into_inner();
+
+    for key in keys.read() {
+        if !key.state.is_pressed() {
+            continue;
+        }
+        match &key.logical_key {
+            Key::Character(typed) => {
+                for c in typed.chars().filter(|&c| Nickname::is_allowed_char(c)) {
+                    if field.0.chars().count() < Nickname::MAX_LEN {
+                        field.0.push(c);
+                    }
+                }
+            }
+            Key::Space if field.0.chars().count() < Nickname::MAX_LEN => field.0.push(' '),
+            Key::Backspace => {
+                field.0.pop();
+            }
+            Key::Enter if !joining => {
+                let task = IoTaskPool::get().spawn(request_join(field.0.clone()));
+                commands.entity(screen).insert(JoinTask(task));
+                joining = true;
+                error_text.0 = JOINING.to_owned();
+                error_color.0 = Color::WHITE;
+            }
+            _ => (),
+        }
+    }
+    if field.is_changed() {
+        field_text.0 = format!("{}_", field.0);
+    }
+}
+
+/// connects once the server answers, or shows why it didn't let us join
+fn poll_join(
+    screen: Single<(Entity, &mut JoinTask), With<JoinScreen>>,
+    error: Single<(&mut Text, &mut TextColor), With<JoinError>>,
+    mut commands: Commands,
+) {
+    let (screen, mut task) = screen.into_inner();
+    let Some(result) = block_on(future::poll_once(&mut task.0)) else {
+        return;
+    };
+    match result {
+        Ok((token, server_addr)) => {
+            crate::connect(&mut commands, token, server_addr);
+            commands.entity(screen).despawn();
+        }
+        Err(e) => {
+            warn!("Couldn't join: {e}");
+            let (mut error_text, mut error_color) = error.into_inner();
+            error_text.0 = e;
+            error_color.0 = ERROR_COLOR;
+            commands.entity(screen).remove::<JoinTask>();
+        }
+    }
+}
+
+/// build-time fallback when the page doesn't say, e.g. `https://mk73.example.com`
+const BUILD_SERVER_URL: Option<&str> = option_env!("MK73_SERVER_URL");
+
+/// where `/join` is
+fn server_url() -> String {
+    #[cfg(target_family = "wasm")]
+    if let Some(url) = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok())
+        .and_then(|params| params.get("server"))
+    {
+        return url;
+    }
+    BUILD_SERVER_URL.map(str::to_owned).unwrap_or_else(|| format!("http://{TCP_ADDR}"))
+}
+
+/// the token and where to connect with it, or why the server rejected us
+async fn request_join(name: String) -> Result<(ConnectToken, SocketAddr), String> {
+    // outside the browser reqwest needs a tokio runtime of its own, blocking the IO thread until the server answers
+    #[cfg(not(target_family = "wasm"))]
+    {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Couldn't start the request: {e}"))?;
+        rt.block_on(post_join(name))
+    }
+    // in the browser it's a `fetch` on the page's event loop
+    #[cfg(target_family = "wasm")]
+    post_join(name).await
+}
+
+async fn post_join(name: String) -> Result<(ConnectToken, SocketAddr), String> {
+    let resp = reqwest::Client::new()
+        .post(format!("{}/join", server_url().trim_end_matches('/')))
+        .body(name)
+        .send()
+        .await
+        .map_err(|e| format!("Couldn't reach the server: {e}"))?;
+    let status = resp.status();
+    let server_addr = resp.headers()
+        .get(GAME_ADDR_HEADER)
+        .and_then(|addr| addr.to_str().ok()?.parse().ok());
+    let body = resp.bytes().await.map_err(|e| format!("Couldn't reach the server: {e}"))?;
+
+    if !status.is_success() {
+        return Err(String::from_utf8_lossy(&body).into_owned());
+    }
+    let token = ConnectToken::try_from_bytes(&body).map_err(|e| format!("Unexpected response from the server: {e}"))?;
+    let server_addr = server_addr.ok_or_else(|| format!("Unexpected response from the server: no {GAME_ADDR_HEADER}"))?;
+    Ok((token, server_addr))
+}
@@ -6,11 +6,14 @@ mod asset;
 mod boat;
 mod dive;
 mod input;
+mod join;
 mod oil_rig;
+mod sonar;
 mod ui;
 mod weapon;
 
 use std::env::current_dir;
+use std::net::SocketAddr;
 use std::sync::LazyLock;
 use std::time::Duration;
 
@@ -19,23 +22,25 @@ use bevy::material::key;
 use bevy::prelude::*;
 use bevy_inspector_egui::bevy_egui::EguiPlugin;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
-use common::protocol::ZIndexUpdate;
+use common::protocol::{AimTurrets, DiveIntent, PingSonar};
 use common::util::{BlockInput, BoatMoveInputExt};
 use common::{
-    Boat, CLIENT_ADDR, MainCamera, MovementPlugin, PROTOCOL_ID, SERVER_ADDR, WorldPlugin,
+    Boat, CLIENT_ADDR, DivePlugin, MainCamera, MovementPlugin, TurretPlugin, WorldPlugin,
     primitives::ZIndex,
     protocol::{Move, ProtocolPlugin, Rotate},
 };
-use common::{TCP_ADDR, UpgradePlugin};
+use common::UpgradePlugin;
 
 use crate::asset::AssetPreloadPlugin;
 use crate::boat::BoatPlugin;
 use crate::dive::DivingPlugin;
+use crate::sonar::SonarPlugin;
 use crate::input::InputBufferPlugin;
+use crate::join::JoinPlugin;
 use crate::oil_rig::OilRigPlugin;
 use crate::ui::UiPlugin;
 use crate::weapon::WeaponPlugin;
-use lightyear::netcode::{Key, NetcodeClient, auth::Authentication};
+use lightyear::netcode::{ConnectToken, NetcodeClient, auth::Authentication};
 use lightyear::prelude::{
     client::{ClientPlugins, NetcodeConfig},
     input::native::{ActionState, InputMarker},
@@ -84,16 +89,22 @@ fn main() -> AppExit {
     .add_plugins(BoatPlugin)
     .add_plugins(InputBufferPlugin)
     .add_plugins(MovementPlugin { move_weapon: true })
+    .add_plugins(TurretPlugin)
+    .add_plugins(DivePlugin)
     .add_plugins(DivingPlugin)
+    .add_plugins(SonarPlugin)
     .add_plugins(WeaponPlugin)
     .add_plugins(UiPlugin)
+    .add_plugins(JoinPlugin)
     .add_plugins(UpgradePlugin)
     // init
     .add_plugins(AssetPreloadPlugin)
     .add_systems(Startup, setup)
     .add_observer(on_added_actionstate::<Rotate>)
     .add_observer(on_added_actionstate::<Move>)
-    .add_observer(on_added_actionstate::<ZIndexUpdate>)
+    .add_observer(on_added_actionstate::<DiveIntent>)
+    .add_observer(on_added_actionstate::<PingSonar>)
+    .add_observer(on_added_actionstate::<AimTurrets>)
     .add_systems(FixedUpdate, update_state)
     .add_systems(Update, move_camera)
     .add_systems(FixedUpdate, sync_z_index)
@@ -118,30 +129,13 @@ static DIGEST: LazyLock<String> = LazyLock::new(|| {
     }
 });
 
-fn setup(mut commands: Commands) {
-    // let client_id = rand::random_range(0..100);
-    let rt = tokio::runtime::Runtime::new().unwrap();
-    let client_id = rt.block_on(async {
-        let resp = reqwest::get(format!("http://{}/client_id", TCP_ADDR))
-            .await
-            .unwrap()
-            .bytes()
-            .await
-            .unwrap();
-
-        u64::from_be_bytes(resp.as_ref().try_into().unwrap())
-    });
-    let auth = Authentication::Manual {
-        server_addr: SERVER_ADDR,
-        client_id,
-        private_key: Key::default(),
-        protocol_id: PROTOCOL_ID,
-    };
+/// connects with the token given by `/join` to `server_addr`, see [`JoinPlugin`]
+///
+/// the client id and timeout come from the token
+fn connect(commands: &mut Commands, token: ConnectToken, server_addr: SocketAddr) {
+    let auth = Authentication::Token(token);
     let netcode_config = NetcodeConfig {
-        // client_timeout_secs: -1,
         num_disconnect_packets: 50,
-        client_timeout_secs: 3,
-        token_expire_secs: -1,
         ..default()
     };
 
@@ -149,7 +143,7 @@ fn setup(mut commands: Commands) {
         .spawn((
             Client::default(),
             LocalAddr(CLIENT_ADDR),
-            PeerAddr(SERVER_ADDR),
+            PeerAddr(server_addr),
             Link::default(),
             NetcodeClient::new(auth, netcode_config).unwrap(),
             WebTransportClientIo {
@@ -162,8 +156,10 @@ fn setup(mut commands: Commands) {
 
     commands.trigger(Connect { entity: client });
 
-    info!("Client {client_id} is requesting");
+    info!("Client is requesting");
+}
 
+fn setup(mut commands: Commands) {
     commands.spawn((
         Camera2d,
         PanCamera {
@@ -292,8 +288,10 @@ fn move_camera(
 }
 
 /// here, we assumeee the modified ZIndex is the correct val to use for rendering
+///
+/// including our own boat, whose ZIndex is predicted by [`DivePlugin`]
 fn sync_z_index(
-    query: Query<(&ZIndex, &mut Transform), (With<Boat>, Without<Controlled>, Changed<ZIndex>)>,
+    query: Query<(&ZIndex, &mut Transform), (With<Boat>, Changed<ZIndex>)>,
 ) {
     for (z_index, mut transform) in query {
         transform.translation.z = z_index.0;
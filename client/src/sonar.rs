@@ -0,0 +1,84 @@
+//! toggles active sonar with `P` and shows its range around our boat, see [`PingSonar`]
+
+use bevy::color::palettes::css::LIME;
+use bevy::input::common_conditions::input_just_pressed;
+use bevy::prelude::*;
+use common::primitives::MeshBundle;
+use common::protocol::PingSonar;
+use common::{ACTIVE_SONAR_RANGE, Boat, CIRCLE_HUD, circle_hud_mesh};
+use lightyear::input::client::InputSystems;
+use lightyear::prelude::Controlled;
+use lightyear::prelude::input::native::{ActionState, InputMarker};
+
+pub(crate) struct SonarPlugin;
+
+impl Plugin for SonarPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveSonar>();
+        // key presses can be missed in `FixedUpdate`, the input is only written from the resource there
+        app.add_systems(Update, (
+            toggle_active_sonar.run_if(input_just_pressed(KeyCode::KeyP)),
+            spawn_sonar_ring,
+            show_sonar_ring.run_if(resource_changed::<ActiveSonar>),
+        ).chain());
+        app.add_systems(FixedPreUpdate, buffer_ping_sonar.in_set(InputSystems::WriteClientInputs));
+    }
+}
+
+/// whether the player turned active sonar on
+#[derive(Resource, Default, Debug)]
+struct ActiveSonar(bool);
+
+/// ring around our boat at [`ACTIVE_SONAR_RANGE`], only shown while pinging
+#[derive(Component)]
+struct SonarRing;
+
+const SONAR_RING_ALPHA: f32 = 0.4;
+
+fn toggle_active_sonar(mut active: ResMut<ActiveSonar>, boat: Single<&Boat, With<Controlled>>) {
+    if !boat.has_active_sonar() {
+        info!("{:?} has no active sonar", *boat);
+        return;
+    }
+    active.0 = !active.0;
+    debug!("Active sonar {}", if active.0 { "on" } else { "off" });
+}
+
+/// turned off when upgrading to a boat without one
+fn buffer_ping_sonar(
+    mut active: ResMut<ActiveSonar>,
+    boat: Single<&Boat, With<Controlled>>,
+    mut ping: Single<&mut ActionState<PingSonar>, With<InputMarker<PingSonar>>>,
+) {
+    if active.0 && !boat.has_active_sonar() {
+        active.0 = false;
+    }
+    if ping.0.0 != active.0 {
+        ping.0 = PingSonar(active.0);
+    }
+}
+
+fn spawn_sonar_ring(
+    boat: Single<Entity, (With<Boat>, Added<Controlled>)>,
+    active: Res<ActiveSonar>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.entity(*boat).with_child((
+        MeshBundle {
+            mesh: Mesh2d(meshes.add(circle_hud_mesh(ACTIVE_SONAR_RANGE))),
+            materials: MeshMaterial2d(materials.add(ColorMaterial::from_color(LIME.with_alpha(SONAR_RING_ALPHA)))),
+        },
+        Transform::from_xyz(0.0, 0.0, *CIRCLE_HUD),
+        if active.0 { Visibility::Inherited } else { Visibility::Hidden },
+        SonarRing,
+        Name::new("Sonar ring"),
+    ));
+}
+
+fn show_sonar_ring(active: Res<ActiveSonar>, rings: Query<&mut Visibility, With<SonarRing>>) {
+    for mut visibility in rings {
+        *visibility = if active.0 { Visibility::Inherited } else { Visibility::Hidden };
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use bevy::{ecs::query::QueryData, input_focus::InputFocus, prelude::*};
 use bevy_inspector_egui::egui::emath::GuiRounding;
 use common::{
@@ -6,7 +8,10 @@ use common::{
         CustomTransform, DisplayScore, Level, Percent, PlayerStats, Size, UpgradeEvent,
         UpgradeRollbackEvent, WeaponCounter, WeaponData,
     },
-    protocol::{Move, Rotate},
+    protocol::{
+        Announcement, BoatDestroyed, DeathReason, Leaderboard, LeaderboardEntry, Move,
+        RespawnRequest, Rotate, SendToServer,
+    },
     util::{BlockInput, pixel, zip_longest},
 };
 use lightyear::prelude::{
@@ -33,12 +38,15 @@ impl Plugin for UiPlugin {
                 .chain(),
         );
         app.add_systems(Update, (recv_stats_update, receive_other_boat_update));
+        app.add_systems(Update, recv_boat_destroyed);
+        app.add_systems(Update, (recv_announcement, expire_announcements));
 
         app.add_observer(show_upgrade);
         app.add_observer(on_choose_upgrade);
         app.add_observer(on_upgrade_rollback);
 
         app.add_plugins(WeaponUiPlugin);
+        app.add_plugins(LeaderboardUiPlugin);
 
         app.insert_state(AfterUpgradeDontClearMoveState::NoNeed);
 
@@ -580,6 +588,96 @@ fn update_upgrade_ui(
         }
     }
 }
+/// shown after our boat sank, until "Play again" is clicked
+#[derive(Debug, Component)]
+struct DeathScreen;
+
+/// clears the per-boat UI and shows the [`DeathScreen`]
+fn recv_boat_destroyed(
+    mut rx: Single<&mut MessageReceiver<BoatDestroyed>>,
+    weapon_selection: Query<Entity, With<WeaponSelection>>,
+    mut upgrade_bar: Single<&mut Visibility, With<UpgradeBar>>,
+    mut commands: Commands,
+) {
+    for BoatDestroyed { reason, score } in rx.receive() {
+        info!("Our boat sank: {reason:?}");
+        for id in weapon_selection {
+            commands.entity(id).despawn();
+        }
+        **upgrade_bar = Visibility::Hidden;
+
+        let reason = match reason {
+            DeathReason::Weapon { weapon, killer } => format!("Sunk by a {weapon:?} from a {killer:?}"),
+            DeathReason::Rammed { killer } => format!("Rammed by a {killer:?}"),
+            DeathReason::OilRig => "Crashed into an oil rig".to_owned(),
+        };
+
+        commands
+            .spawn((
+                Node {
+                    margin: UiRect::all(auto()),
+                    padding: UiRect::all(px(20)),
+                    row_gap: px(ROW_GAP),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                BackgroundColor(Color::linear_rgba(0.0, 0.0, 0.0, 0.5)),
+                Name::new("DeathScreen"),
+                DeathScreen,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    TextFont {
+                        font: FontSource::SansSerif,
+                        font_size: FONT_SIZE,
+                        weight: FontWeight::MEDIUM,
+                        ..default()
+                    },
+                    Text(format!("{reason}\nScore: {score}")),
+                    TextLayout {
+                        justify: Justify::Center,
+                        ..default()
+                    },
+                ));
+                parent
+                    .spawn((
+                        Node {
+                            padding: PADDING,
+                            ..default()
+                        },
+                        Button, // to block user input
+                        BackgroundColor(HOVER_BACKGROUND),
+                        children![(
+                            TextFont {
+                                font: FontSource::SansSerif,
+                                font_size: FONT_SIZE,
+                                ..default()
+                            },
+                            Text::new("Play again"),
+                        )],
+                    ))
+                    .observe(click_play_again);
+            });
+    }
+}
+
+/// the new boat respawns the weapon selection bar once its [`WeaponCounter`] is added
+fn click_play_again(
+    _trigger: On<Pointer<Click>>,
+    mut sender: Single<&mut MessageSender<RespawnRequest>>,
+    death_screen: Single<Entity, With<DeathScreen>>,
+    mut progress_bar: Single<(&mut Text, &mut BackgroundGradient), With<ProgressBar>>,
+    mut commands: Commands,
+) {
+    debug!("Requesting respawn");
+    sender.send::<SendToServer>(RespawnRequest);
+    commands.entity(*death_screen).despawn();
+
+    let (ref mut text, ref mut background) = *progress_bar;
+    update_percent(0, Level::One, text, background);
+}
+
 struct WeaponUiPlugin;
 
 impl Plugin for WeaponUiPlugin {
@@ -606,9 +704,10 @@ struct WeaponSelectionIndividualImage;
 #[derive(Debug, Component)]
 struct WeaponSelectionIndividualBox(Weapon);
 
+/// rebuilds the weapon selection bar from the controlled boat's [`WeaponCounter`]
 #[derive(Event)]
-struct UpdateWeaponSelectionBar {
-    target: Boat,
+pub(crate) struct UpdateWeaponSelectionBar {
+    pub target: Boat,
 }
 
 /// UI event that updates the UI Display of aval/max of a weapon
@@ -878,6 +977,185 @@ fn update_selection_bar_count(
     Ok(())
 }
 
+/// how long an [`Announcement`] stays on screen
+const ANNOUNCEMENT_DURATION: Duration = Duration::from_secs(8);
+
+/// top centre, despawned when the timer finishes
+#[derive(Debug, Component, Deref, DerefMut)]
+struct AnnouncementBanner(Timer);
+
+/// a newer announcement replaces the one on screen
+fn recv_announcement(
+    mut rx: Single<&mut MessageReceiver<Announcement>>,
+    banners: Query<Entity, With<AnnouncementBanner>>,
+    mut commands: Commands,
+) {
+    let Some(Announcement(text)) = rx.receive().last() else {
+        return;
+    };
+    info!("Announcement: {text}");
+    for banner in &banners {
+        commands.entity(banner).despawn();
+    }
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(PADDING_TOP * 4),
+            width: percent(100),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        AnnouncementBanner(Timer::new(ANNOUNCEMENT_DURATION, TimerMode::Once)),
+        children![(
+            Text::new(text),
+            TextFont {
+                font: FontSource::SansSerif,
+                font_size: FontSize::Px(FONT_SIZE_PX * 1.5),
+                ..default()
+            },
+            TextColor(TEXT_SELECTED),
+            BackgroundColor(HOVER_BACKGROUND),
+            Node {
+                padding: PADDING,
+                ..default()
+            },
+        )],
+    ));
+}
+
+fn expire_announcements(
+    banners: Query<(&mut AnnouncementBanner, Entity)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (mut banner, id) in banners {
+        if banner.tick(time.delta()).is_finished() {
+            commands.entity(id).despawn();
+        }
+    }
+}
+
+struct LeaderboardUiPlugin;
+
+impl Plugin for LeaderboardUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_leaderboard.after(crate::setup))
+            .add_systems(Update, recv_leaderboard);
+    }
+}
+
+/// top right, rows are rebuilt on every [`Leaderboard`]
+#[derive(Debug, Component)]
+struct LeaderboardPanel;
+
+/// pixels, boat sprites are scaled down to this height
+const LEADERBOARD_SPRITE_HEIGHT: f32 = 20.0;
+
+fn spawn_leaderboard(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(PADDING_TOP),
+            right: px(PADDING_TOP),
+            flex_direction: FlexDirection::Column,
+            padding: PADDING,
+            border_radius: BorderRadius::all(px(8)),
+            ..default()
+        },
+        BackgroundColor(HOVER_BACKGROUND),
+        LeaderboardPanel,
+    ));
+}
+
+fn recv_leaderboard(
+    mut rx: Single<&mut MessageReceiver<Leaderboard>>,
+    panel: Single<Entity, With<LeaderboardPanel>>,
+    sprites: Res<SpriteUiMap>,
+    mut commands: Commands,
+) {
+    // sequenced, only the latest matters
+    let Some(leaderboard) = rx.receive().last() else {
+        return;
+    };
+    let own_rank = leaderboard.own.as_ref().map(|(rank, _)| *rank as usize);
+
+    let mut panel = commands.entity(*panel);
+    panel.despawn_related::<Children>();
+    panel.with_children(|panel| {
+        for (index, entry) in leaderboard.top.iter().enumerate() {
+            let rank = index + 1;
+            panel.spawn(leaderboard_row(rank, entry, own_rank == Some(rank), &sprites));
+        }
+        // still shows where the player is when outside the top
+        if let Some((rank, entry)) = &leaderboard.own
+            && *rank as usize > leaderboard.top.len()
+        {
+            panel.spawn((Text::new("..."), leaderboard_font(), TextColor(TEXT_LIGHT)));
+            panel.spawn(leaderboard_row(*rank as usize, entry, true, &sprites));
+        }
+    });
+}
+
+fn leaderboard_font() -> TextFont {
+    TextFont {
+        font: FontSource::SansSerif,
+        font_size: FONT_SIZE,
+        style: FontStyle::Normal,
+        ..default()
+    }
+}
+
+fn leaderboard_row(rank: usize, entry: &LeaderboardEntry, own: bool, sprites: &SpriteUiMap) -> impl Bundle {
+    let color = TextColor(if own { TEXT_SELECTED } else { TEXT_LIGHT });
+    let (width, height) = sprites
+        .get_size(entry.boat)
+        .map(|size| (size.width() as f32, size.height() as f32))
+        .unwrap_or((LEADERBOARD_SPRITE_HEIGHT, LEADERBOARD_SPRITE_HEIGHT));
+    let scale = LEADERBOARD_SPRITE_HEIGHT / height;
+    (
+        Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: px(ROW_GAP),
+            ..default()
+        },
+        BackgroundColor(if own { PRESSED_BACKGROUND } else { NORMAL_BACKGROUND }),
+        children![
+            (Text::new(format!("{rank}.")), leaderboard_font(), color),
+            (
+                ImageNode {
+                    image: sprites.image(),
+                    texture_atlas: sprites.get(entry.boat),
+                    ..default()
+                },
+                Node {
+                    width: px(width * scale),
+                    height: px(LEADERBOARD_SPRITE_HEIGHT),
+                    ..default()
+                },
+            ),
+            (
+                Text::new(entry.name.clone()),
+                leaderboard_font(),
+                color,
+                Node {
+                    flex_grow: 1.0,
+                    ..default()
+                },
+            ),
+            (
+                Text::new(entry.score.to_string()),
+                leaderboard_font(),
+                color,
+                TextLayout {
+                    justify: Justify::Right,
+                    ..default()
+                },
+            ),
+        ],
+    )
+}
+
 const TEXT_LIGHT: Color = Color::linear_rgba(1.0, 1.0, 1.0, 0.5);
 const TEXT_SELECTED: Color = Color::linear_rgba(1.0, 1.0, 1.0, 1.0);
 // const NORMAL: Color = Color::linear_rgb(0.3, 0.3, 0.3);
@@ -1,15 +1,18 @@
 use crate::FiresWeapon;
 use crate::asset::SpriteMap;
 use crate::ui::UpdateWeaponSelectionBarCount;
-use bevy::color::palettes::css::LIME;
+use bevy::color::palettes::css::{LIME, WHITE};
 use bevy::prelude::*;
 use common::macros::force_single;
 use common::primitives::{
-    CursorPos, LastSpeed, MeshBundle, Size, Speed, TargetRotation, WeaponCounter, WrapRadian as _,
+    CursorPos, CustomTransform, LastSpeed, MeshBundle, Size, Speed, TargetRotation, WeaponCounter, WrapRadian as _, ZIndex,
 };
-use common::protocol::{EntityOnClient, ReloadWeapon, SendToServer, SpawnWeapon, WeaponRollBack};
+use common::protocol::{EntityOnClient, ReloadWeapon, SendToServer, SpawnWeapon, WeaponImpact, WeaponRollBack, WeaponSpoofed};
 use common::util::get_rotate_radian;
-use common::{Boat, CIRCLE_HUD, Weapon};
+use common::{
+    Ballistic, Boat, CIRCLE_HUD, Domain, Landed, LaunchedBy, OCEAN_SURFACE, SHELL_SPLASH_RADIUS, Seeker, Sinking, Spoofed, Staged, Staging,
+    Travelled, Turrets, Weapon, WeaponType,
+};
 use lightyear::prelude::*;
 use std::debug_assert_matches;
 
@@ -20,13 +23,17 @@ impl Plugin for WeaponPlugin {
     fn build(&self, app: &mut App) {
         app.add_observer(fire_weapon)
             .add_observer(spawn_others_weapon)
+            .add_observer(spawn_released_payload)
+            .add_observer(spawn_splash)
             .add_systems(FixedUpdate, rollback)
-            .add_systems(Update, sync_weapon_marker);
+            .add_systems(Update, (sync_weapon_marker, arc_shells, fade_splashes));
 
         app.add_observer(change_weapon);
 
         // messages MUST be Update or PreUpdate .after(Receive)
         app.add_systems(Update, recv_reload); // syncing weapon reloads aren't that scheduling sensitive
+        app.add_systems(Update, recv_impact);
+        app.add_systems(Update, recv_spoofed);
     }
 }
 
@@ -35,25 +42,42 @@ fn fire_weapon(
     _: On<FiresWeapon>,
     cursor_pos: Res<CursorPos>,
     mut sender: Single<&mut MessageSender<SpawnWeapon>>,
-    boat: Single<(&Transform, &mut WeaponCounter), (With<Controlled>, With<Boat>)>,
-    client_id: Single<&LocalId>,
+    boat: Single<(&Transform, &CustomTransform, Option<&Turrets>, &mut WeaponCounter, &Boat, Entity), (With<Controlled>, Without<Sinking>)>,
 
     mut commands: Commands,
     sprites: Res<SpriteMap>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    let (transform, mut weapon_counter) = boat.into_inner();
+    let (transform, custom, turrets, mut weapon_counter, boat, boat_id) = boat.into_inner();
     let Some(selected) = weapon_counter.selected_weapon else {
         return;
     };
+    let mount = turrets.and_then(|turrets| {
+        let index = boat.best_mount(selected.weapon_type(), custom, turrets, cursor_pos.0)?;
+        Some((index, turrets.world_azimuth(index, custom)))
+    });
+    let (position, starting_rotation) = match mount {
+        Some((index, azimuth)) => (boat.mounts()[index].world_position(custom).extend(transform.translation.z), azimuth),
+        None if selected.weapon_type() == WeaponType::DepthCharge => (
+            boat.stern(transform.translation.xy(), transform.rotation.wrap_radian()).extend(transform.translation.z),
+            transform.rotation.wrap_radian(),
+        ),
+        None => (transform.translation, transform.rotation.wrap_radian()),
+    };
+    // turrets fire where they point, fixed mounts and the centre let the weapon turn towards the cursor
+    let end_rotation = match mount {
+        Some((index, azimuth)) if !boat.mounts()[index].is_fixed() => azimuth,
+        _ => get_rotate_radian(position.xy(), cursor_pos.0).wrap_radian(),
+    };
     let mut msg = SpawnWeapon {
         weapon: selected,
-        position: transform.translation, // currently starts at centre of boat
-        starting_rotation: transform.rotation.wrap_radian(),
-        end_rotation: get_rotate_radian(transform.translation.xy(), cursor_pos.0).wrap_radian(),
+        position,
+        mount: mount.map(|(index, _)| index as u8),
+        starting_rotation,
+        end_rotation,
+        target: cursor_pos.0,
         entity_on_client: EntityOnClient(u64::MAX),
-        client_id: client_id.0,
     };
 
     let Some(count) = weapon_counter.weapons.get_mut(&selected) else {
@@ -70,28 +94,36 @@ fn fire_weapon(
         target_weapon: selected,
     }); // TODO seeing as WeaponData and others are bundled together, Changed<T> doesn't work really well. triggering this event everywhere weapon data changes fro nwo...
 
-    msg.entity_on_client.0 = commands
-        .spawn((
-            Sprite {
-                // weapons get spawned frequently
-                image: sprites.image(),
-                custom_size: Some(msg.weapon.render_size()),
-                texture_atlas: sprites.get(msg.weapon),
-                ..default()
-            },
-            Transform {
-                translation: transform.translation,
-                // follows boat rotation
-                rotation: transform.rotation,
-                ..default()
-            },
-            TargetRotation(msg.end_rotation),
-            LastSpeed(Speed::ZERO),
-            msg.weapon,
-            Name::new("Controlled weapon"),
-        ))
-        .id()
-        .to_bits();
+    let mut weapon = commands.spawn((
+        Sprite {
+            // weapons get spawned frequently
+            image: sprites.image(),
+            custom_size: Some(msg.weapon.render_size()),
+            texture_atlas: sprites.get(msg.weapon),
+            ..default()
+        },
+        Transform {
+            translation: msg.position,
+            // follows the mount or the boat
+            rotation: msg.starting_rotation.to_quat(),
+            ..default()
+        },
+        TargetRotation(msg.end_rotation),
+        LastSpeed(Speed::ZERO),
+        LaunchedBy(boat_id),
+        msg.weapon,
+        Name::new("Controlled weapon"),
+    ));
+    if let Some(seeker) = Seeker::new(msg.weapon) {
+        weapon.insert(seeker);
+    }
+    if let Some(staging) = Staging::new(msg.weapon, msg.position.xy(), msg.target) {
+        weapon.insert(staging);
+    }
+    if let Some((ballistic, heading)) = Ballistic::new(msg.weapon, msg.position.xy(), msg.target, msg.end_rotation, None) {
+        weapon.insert((ballistic, TargetRotation(heading)));
+    }
+    msg.entity_on_client.0 = weapon.id().to_bits();
 
     // at back to prevent use-after-move
 
@@ -129,6 +161,47 @@ fn spawn_others_weapon(
         },
         Name::new("Other's weapon"),
     ));
+    if let Some(seeker) = Seeker::new(*weapon) {
+        commands.entity(trigger.entity).insert(seeker);
+    }
+
+    spawn_weapon_marker(
+        &mut commands,
+        trigger.entity,
+        transform.translation.xy(),
+        &mut meshes,
+        &mut materials,
+    );
+}
+
+/// the rocket stage our own payload was released by, which the server keeps identifying it with
+#[derive(Debug, Component)]
+struct ReleasedBy(Entity);
+
+/// only our own weapons are [`Staging`], others' payloads are replicated
+fn spawn_released_payload(
+    trigger: On<Staged>,
+    weapons: Query<(&Weapon, &Transform)>,
+    mut commands: Commands,
+
+    sprites: Res<SpriteMap>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Ok((weapon, transform)) = weapons.get(trigger.entity) else {
+        return;
+    };
+
+    commands.entity(trigger.entity).insert((
+        Sprite {
+            image: sprites.image(),
+            custom_size: Some(weapon.render_size()),
+            texture_atlas: sprites.get(*weapon),
+            ..default()
+        },
+        ReleasedBy(trigger.stage),
+        Name::new("Controlled weapon"),
+    ));
 
     spawn_weapon_marker(
         &mut commands,
@@ -159,7 +232,7 @@ fn change_weapon(
 fn rollback(
     mut reader: Single<&mut MessageReceiver<WeaponRollBack>>,
     mut commands: Commands,
-    mut weapons: Query<&mut Transform, With<Weapon>>,
+    mut weapons: Query<(&mut Transform, &mut TargetRotation, Option<&mut Ballistic>), With<Weapon>>,
     types: Query<&Weapon>,
     mut counter: Single<&mut WeaponCounter, With<Controlled>>,
 ) {
@@ -169,13 +242,19 @@ fn rollback(
             WeaponRollBack::Transform {
                 position,
                 rotation,
+                end_rotation,
+                ballistic,
                 entity,
             } => {
-                let Ok(mut transform) = weapons.get_mut(Entity::from_bits(entity.0)) else {
+                let Ok((mut transform, mut target_rotation, ours)) = weapons.get_mut(Entity::from_bits(entity.0)) else {
                     return;
                 };
                 transform.translation = position;
                 transform.rotation = rotation.to_quat();
+                target_rotation.0 = end_rotation;
+                if let (Some(mut ours), Some(ballistic)) = (ours, ballistic) {
+                    *ours = ballistic;
+                }
             }
             WeaponRollBack::Despawn { entity } => {
                 if let Ok(mut weapon) = commands.get_entity(Entity::from_bits(entity.0)) {
@@ -190,6 +269,37 @@ fn rollback(
     }
 }
 
+/// others' weapons are despawned through replication, but our own copy is simulated locally
+fn recv_impact(
+    mut reader: Single<&mut MessageReceiver<WeaponImpact>>,
+    payloads: Query<(&ReleasedBy, Entity)>,
+    mut commands: Commands,
+) {
+    for WeaponImpact { entity } in reader.receive() {
+        debug!("Weapon hit");
+        let entity = Entity::from_bits(entity.0);
+        if let Ok(mut weapon) = commands.get_entity(entity) {
+            weapon.despawn();
+        }
+        for (_, payload) in payloads.iter().filter(|(released_by, _)| released_by.0 == entity) {
+            commands.entity(payload).despawn();
+        }
+    }
+}
+
+/// others' torpedoes receive the replicated [`Spoofed`], ours is told separately
+fn recv_spoofed(
+    mut reader: Single<&mut MessageReceiver<WeaponSpoofed>>,
+    mut commands: Commands,
+) {
+    for WeaponSpoofed { entity, decoy } in reader.receive() {
+        debug!("Torpedo lured by decoy {decoy}");
+        if let Ok(mut weapon) = commands.get_entity(Entity::from_bits(entity.0)) {
+            weapon.insert(Spoofed(decoy));
+        }
+    }
+}
+
 #[force_single]
 fn recv_reload(
     #[force_single_skip(lazy)] mut rx: Single<&mut MessageReceiver<ReloadWeapon>>,
@@ -217,6 +327,63 @@ fn recv_reload(
     }
 }
 
+/// how much bigger a shell is drawn at the top of its arc
+const SHELL_ARC_SCALE: f32 = 0.6;
+/// seconds a splash stays on the water
+const SPLASH_DURATION: f32 = 0.8;
+
+/// grows shells towards the top of their arc, there's no height in the world
+fn arc_shells(shells: Query<(&mut Sprite, &Weapon, &Travelled, &Ballistic)>) {
+    for (mut sprite, weapon, travelled, ballistic) in shells {
+        sprite.custom_size = Some(weapon.render_size() * (1.0 + SHELL_ARC_SCALE * ballistic.height(travelled)));
+    }
+}
+
+/// a missed shot, hits are shown by the boat's health
+#[derive(Debug, Component)]
+struct Splash(Timer);
+
+/// only guesses at misses, the server decides the damage
+fn spawn_splash(
+    trigger: On<Landed>,
+    boats: Query<(&CustomTransform, &Boat, &ZIndex), Without<Sinking>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let hit = boats.iter().any(|(custom, boat, z)| {
+        Domain::of_depth(*z) == Domain::Surface
+            && boat.keel_distance(custom.position.0, custom.rotation, trigger.position) < SHELL_SPLASH_RADIUS
+    });
+    if hit {
+        return;
+    }
+
+    commands.spawn((
+        Transform::from_translation(trigger.position.extend(*OCEAN_SURFACE)),
+        MeshBundle {
+            mesh: Mesh2d(meshes.add(Circle::new(SHELL_SPLASH_RADIUS / 2.0))),
+            materials: MeshMaterial2d(materials.add(ColorMaterial::from_color(WHITE.with_alpha(0.6)))),
+        },
+        Splash(Timer::from_seconds(SPLASH_DURATION, TimerMode::Once)),
+        Name::new("Splash"),
+    ));
+}
+
+fn fade_splashes(
+    mut commands: Commands,
+    splashes: Query<(&mut Splash, &mut Transform, Entity)>,
+    time: Res<Time>,
+) {
+    for (mut splash, mut transform, id) in splashes {
+        if splash.0.tick(time.delta()).is_finished() {
+            commands.entity(id).despawn();
+            continue;
+        }
+        transform.scale = Vec3::splat(1.0 + splash.0.fraction());
+    }
+}
+
 const MARKER_OFFSET: Vec2 = vec2(0.0, 40.0);
 const MARKER_BOTTOM: Vec2 = vec2(0.0, -17.32);
 
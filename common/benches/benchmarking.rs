@@ -1,7 +1,21 @@
 use std::hint::black_box;
 use bevy::math::{Vec2, vec2};
 use criterion::{criterion_group, criterion_main, Criterion};
-use common::{collision::{out_of_bound_no_rotation, out_of_bound_point, out_of_bounds}, primitives::{Mk48Rect, Radian}, util::{avaliable_cords, tiles_around_point}, WorldSize};
+use bevy::ecs::entity::Entity;
+use common::{collision::{out_of_bound_no_rotation, out_of_bound_point, out_of_bounds}, grid::SpatialGrid, primitives::{Mk48Rect, Radian}, util::{avaliable_cords, seeded_unit, tiles_around_point}, WorldSize};
+
+/// boats and points on a map of 400+ players, see [`WorldSize`]
+const BOATS: u64 = 500;
+const POINTS: u64 = 5000;
+const WORLD: Vec2 = vec2(6500.0, 5000.0);
+/// pixels, around a destroyer's circle hud
+const REACH: f32 = 150.0;
+
+fn scatter(count: u64, salt: u64) -> Vec<(Entity, Vec2)> {
+    (0..count)
+        .map(|i| (Entity::from_bits(i), vec2(seeded_unit(i, salt), seeded_unit(i, salt + 1)) * WORLD / 2.0))
+        .collect()
+}
 
 fn criterion_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("Collisions");
@@ -26,6 +40,28 @@ fn criterion_benchmark(c: &mut Criterion) {
         avaliable_cords(black_box(vec2(10.0, 10.0)), black_box(10.0))
     }));
     group.finish();
+
+    let mut group = c.benchmark_group("Points near boats");
+    let boats = scatter(BOATS, 0);
+    let points = scatter(POINTS, 2);
+    group.bench_function("Every boat for every point", |b| b.iter(|| {
+        points.iter()
+            .filter(|(_, point)| boats.iter().any(|(_, boat)| boat.distance(*point) <= REACH))
+            .count()
+    }));
+    group.bench_function("Rebuilt grid", |b| {
+        let mut grid = SpatialGrid::<()>::default();
+        b.iter(|| {
+            grid.clear();
+            for &(boat, position) in &boats {
+                grid.insert(boat, position);
+            }
+            points.iter()
+                .filter(|(_, point)| grid.within(*point, black_box(REACH)).next().is_some())
+                .count()
+        })
+    });
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
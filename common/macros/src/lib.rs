@@ -5,7 +5,7 @@ use std::{ops::Not, sync::LazyLock};
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Literal, Span, TokenStream as TokenStream2};
 use quote::quote;
-use syn::{Data, DataEnum, DeriveInput, Expr, ExprLit, FnArg, ItemFn, Lit, LitFloat, LitInt, Meta as SynMeta, MetaList, MetaNameValue, Pat, Path, Stmt, Token, Type, TypePath, Variant, parse_macro_input, punctuated::Punctuated, spanned::Spanned, token::Comma};
+use syn::{Data, DataEnum, DeriveInput, Expr, ExprCall, ExprLit, ExprUnary, FnArg, ItemFn, Lit, LitFloat, LitInt, Meta as SynMeta, MetaList, MetaNameValue, Pat, Path, Stmt, Token, Type, TypePath, UnOp, Variant, parse_macro_input, punctuated::Punctuated, spanned::Spanned, token::Comma};
 use helper::absolute_path;
 
 use crate::helper::SpriteSheet;
@@ -22,7 +22,10 @@ macro_rules! bail {
     };
     (?span = $span:expr, $text:expr) => {
         return ::syn::Error::new($span, $text).into_compile_error()
-    }
+    };
+    (?span = $span:expr, $text:expr, ?2) => {
+        return (::proc_macro2::TokenStream::new(), ::syn::Error::new($span, $text).into_compile_error())
+    };
 }
 
 const SPRITE_JSON: &str = include_str!("../../../client/assets/spritesheet.json");
@@ -46,9 +49,26 @@ static SHEET: LazyLock<SpriteSheet> = LazyLock::new(|| serde_json::from_str(SPRI
 ///     Olympias
 /// }
 /// ```
+/// 
+/// optional handling stats, defaults used if omitted:
+/// ```ignore
+/// #[acceleration = 1.5]   // knots gained per tick, default 1
+/// #[turn_rate = 0.8]      // degrees per tick, default `DEFAULT_MAX_TURN_DEG`
+/// #[dive_speed = 0.004]   // raw ZIndex per tick, default 0.004
+/// #[max_depth = 0.3]      // positive ZIndex, up to the ocean floor, default the ocean floor
+/// #[reverse_ratio = 0.5]  // of max speed in reverse, within (0, 1], default 0.6
+/// ```
+///
+/// optional weapon mounts, see `turret::Mount`, boats without any fire everything from their centre:
+/// ```ignore
+/// #[mounts(
+///     turret(Shell, 45, 0, 0, 150, 0.6),  // weapon type, x and y in meters, rest, arc and turn rate in degrees
+///     fixed(Torpedo, -5, 2, 90),          // weapon type, x and y in meters, rest in degrees
+/// )]
+/// ```
 #[proc_macro_derive(
     BoatImpl,
-    attributes(json, armanents, default_armanent, level)
+    attributes(json, armanents, default_armanent, level, acceleration, turn_rate, dive_speed, max_depth, reverse_ratio, mounts)
 )]
 pub fn derive_boat_methods(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
@@ -152,6 +172,17 @@ pub fn derive_weapon_reload(input: TokenStream) -> TokenStream {
 
     TokenStream::from(derive_weapon_reload_inner(variants, ident))
 }
+/// either `#[range = <meters>]` for moving weapons or `#[lifetime = <seconds>]` for static ones, exactly one per variant
+#[proc_macro_derive(Expiry, attributes(range, lifetime))]
+pub fn derive_weapon_expiry(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    let Data::Enum(DataEnum { variants, .. }) = ast.data else {
+        panic!("Only enums supported")
+    };
+    let ident = ast.ident;
+
+    TokenStream::from(derive_weapon_expiry_inner(variants, ident))
+}
 
 #[proc_macro_attribute]
 pub fn force_single(_attr: TokenStream, input: TokenStream) -> TokenStream {
@@ -284,12 +315,17 @@ fn impl_boat(ast: DeriveInput) -> proc_macro2::TokenStream {
 
     let armanents = derive_armanents(&variants);
     let (assertions, level) = derive_level(&variants);
+    let (handling_assertions, handling) = derive_handling(&variants);
+    let mounts = derive_mounts(&variants);
     
     quote! {
         #assertions
+        #handling_assertions
         impl #name #generics {
             #armanents
             #level
+            #handling
+            #mounts
         }
     }
 }
@@ -397,6 +433,7 @@ fn derive_armanents(variants: &Punctuated<Variant, Comma>) -> TokenStream2 {
     let mut match_arms = vec![];
     let mut default_weapon_arms = vec![];
     let (weapon_path, hashmap_path, weapon_data_path) = (absolute_path("Weapon"), absolute_path("util::OrderedHashMap"), absolute_path("primitives::WeaponData"));
+    let overridable_path = absolute_path("definitions::Overridable");
 
     for variant in variants {
         let ident = &variant.ident;
@@ -504,11 +541,19 @@ fn derive_armanents(variants: &Punctuated<Variant, Comma>) -> TokenStream2 {
 
     quote!(
         pub fn armanents(&self) -> #hashmap_path<#weapon_path, #weapon_data_path>{
+            if let ::std::option::Option::Some(armanents) = <Self as #overridable_path>::armanents_override(self) {
+                return armanents.iter()
+                    .map(|&(weapon, count)| (weapon, #weapon_data_path { max: count, avaliable: count }))
+                    .collect();
+            }
             match self {
                 #(#match_arms),*
             }
         }
         pub fn default_weapon(&self) -> ::std::option::Option<#weapon_path> {
+            if let ::std::option::Option::Some(default) = <Self as #overridable_path>::default_weapon_override(self) {
+                return default;
+            }
             match self {
                 #(#default_weapon_arms),*
             }
@@ -519,7 +564,7 @@ fn derive_armanents(variants: &Punctuated<Variant, Comma>) -> TokenStream2 {
 /// returns (assertions, impl code)
 fn derive_level(variants: &Punctuated<Variant, Comma>) -> (TokenStream2, TokenStream2) {
     let mut match_arms = vec![];
-    let level_path = absolute_path("primitives::Level");
+    let (level_path, overridable_path) = (absolute_path("primitives::Level"), absolute_path("definitions::Overridable"));
     let mut const_asserts = vec![];
 
     for variant in variants {
@@ -549,6 +594,9 @@ fn derive_level(variants: &Punctuated<Variant, Comma>) -> (TokenStream2, TokenSt
 
     let level_impl = quote! {
         pub fn level(&self) -> #level_path {
+            if let ::std::option::Option::Some(level) = <Self as #overridable_path>::level_override(self) {
+                return #level_path::try_from_u8(level).expect("Validated when loading definitions");
+            }
             match self {
                 #(#match_arms),*
             }
@@ -561,6 +609,237 @@ fn derive_level(variants: &Punctuated<Variant, Comma>) -> (TokenStream2, TokenSt
     (const_assert, level_impl)
 }
 
+/// `#[name = int/float]`, possibly negative, None if not specified
+fn parse_f32_attr(variant: &Variant, name: &str) -> Result<Option<f32>, syn::Error> {
+    let mut attrs = variant.attrs.iter().filter(|attr| attr.path().is_ident(name));
+    let Some(attr) = attrs.next() else {
+        return Ok(None);
+    };
+    if let Some(duplicate) = attrs.next() {
+        return Err(syn::Error::new(duplicate.span(), format!("Duplicate #[{name}]")));
+    }
+
+    let SynMeta::NameValue(MetaNameValue { value, .. }) = &attr.meta else {
+        return Err(syn::Error::new(attr.span(), format!("Expected #[{name} = int/float]")));
+    };
+    parse_f32_expr(value)
+        .map(Some)
+        .map_err(|e| syn::Error::new(e.span(), format!("#[{name}]: {e}")))
+}
+
+/// an int or float literal, possibly negative
+fn parse_f32_expr(expr: &Expr) -> Result<f32, syn::Error> {
+    // `-1` is a negated literal rather than a literal
+    let (negative, unsigned) = match expr {
+        Expr::Unary(ExprUnary { op: UnOp::Neg(_), expr, .. }) => (true, &**expr),
+        expr => (false, expr),
+    };
+    let parsed = match unsigned {
+        Expr::Lit(ExprLit { lit: Lit::Float(float), .. }) => float.base10_parse::<f32>()?,
+        Expr::Lit(ExprLit { lit: Lit::Int(int), .. }) => int.base10_parse::<f32>()?,
+        _ => return Err(syn::Error::new(expr.span(), "Expected int/float, got a non-numeric value")),
+    };
+    Ok(if negative { -parsed } else { parsed })
+}
+
+/// `Boat::mounts` from every `#[mounts(..)]`, in order
+fn derive_mounts(variants: &Punctuated<Variant, Comma>) -> TokenStream2 {
+    let mount_path = absolute_path("turret::Mount");
+    let weapon_type_path = absolute_path("WeaponType");
+
+    let mut match_arms = vec![];
+    for variant in variants {
+        let ident = &variant.ident;
+        let mut mounts = vec![];
+        for attr in variant.attrs.iter().filter(|attr| attr.path().is_ident("mounts")) {
+            let calls = match attr.parse_args_with(Punctuated::<ExprCall, Comma>::parse_terminated) {
+                Ok(calls) => calls,
+                Err(e) => return e.into_compile_error(),
+            };
+            for call in &calls {
+                match parse_mount(call) {
+                    Ok((constructor, weapon_type, numbers)) => mounts.push(quote! {
+                        #mount_path::#constructor(#weapon_type_path::#weapon_type, #(#numbers),*)
+                    }),
+                    Err(e) => return e.into_compile_error(),
+                }
+            }
+        }
+        if !mounts.is_empty() {
+            // const fn calls are only promoted to 'static in a const
+            match_arms.push(quote! {
+                Self::#ident => {
+                    const MOUNTS: &[#mount_path] = &[#(#mounts),*];
+                    MOUNTS
+                }
+            });
+        }
+    }
+    let fallback = (match_arms.len() < variants.len()).then(|| quote! { _ => &[], });
+
+    quote! {
+        /// where weapons leave the hull, empty for boats firing everything from their centre
+        pub fn mounts(&self) -> &'static [#mount_path] {
+            match self {
+                #(#match_arms),*
+                #fallback
+            }
+        }
+    }
+}
+
+/// (constructor, weapon type, numbers) of `turret(Shell, x, y, rest, arc, turn_rate)` or `fixed(Torpedo, x, y, rest)`
+fn parse_mount(call: &ExprCall) -> Result<(Ident, Ident, Vec<f32>), syn::Error> {
+    let constructor = match &*call.func {
+        Expr::Path(path) => path.path.get_ident().cloned(),
+        _ => None,
+    };
+    let arity = match constructor.as_ref().map(Ident::to_string).as_deref() {
+        Some("turret") => 6,
+        Some("fixed") => 4,
+        _ => return Err(syn::Error::new(call.func.span(), "Expected turret(..) or fixed(..)")),
+    };
+    if call.args.len() != arity {
+        return Err(syn::Error::new(call.span(), format!("Expected {arity} arguments, got {}", call.args.len())));
+    }
+
+    let mut args = call.args.iter();
+    let weapon_type = match args.next() {
+        Some(Expr::Path(path)) => path.path.get_ident().cloned(),
+        _ => None,
+    }
+    .ok_or_else(|| syn::Error::new(call.args[0].span(), "Expected a weapon type, e.g. Shell"))?;
+    let numbers = args.map(parse_f32_expr).collect::<Result<_, _>>()?;
+
+    Ok((constructor.unwrap(), weapon_type, numbers))
+}
+
+/// returns (assertions, impl code) of `acceleration`, `max_turn`, `diving_speed`, `max_depth` and `reverse_ratio`
+fn derive_handling(variants: &Punctuated<Variant, Comma>) -> (TokenStream2, TokenStream2) {
+    const DEFAULT_ACCELERATION: f32 = 1.0;
+    const DEFAULT_DIVE_SPEED: f32 = 0.004;
+    const DEFAULT_REVERSE_RATIO: f32 = 0.6;
+
+    let speed_path = absolute_path("primitives::Speed");
+    let radian_path = absolute_path("primitives::Radian");
+    let z_index_path = absolute_path("primitives::ZIndex");
+    let default_turn_path = absolute_path("DEFAULT_MAX_TURN_DEG");
+    let floor_path = absolute_path("OCEAN_FLOOR");
+
+    let mut acceleration_arms = vec![];
+    let mut turn_arms = vec![];
+    let mut dive_arms = vec![];
+    let mut depth_arms = vec![];
+    let mut reverse_arms = vec![];
+    let mut const_asserts = vec![];
+
+    for variant in variants {
+        let ident = &variant.ident;
+        let parse = |name| parse_f32_attr(variant, name);
+        let (acceleration, turn_rate, dive_speed, max_depth, reverse_ratio) = match (
+            parse("acceleration"),
+            parse("turn_rate"),
+            parse("dive_speed"),
+            parse("max_depth"),
+            parse("reverse_ratio"),
+        ) {
+            (Ok(a), Ok(t), Ok(d), Ok(m), Ok(r)) => (a, t, d, m, r),
+            (Err(e), ..) | (_, Err(e), ..) | (_, _, Err(e), ..) | (.., Err(e), _) | (.., Err(e)) => {
+                return (TokenStream2::new(), e.into_compile_error())
+            }
+        };
+
+        let acceleration = acceleration.unwrap_or(DEFAULT_ACCELERATION);
+        if acceleration <= 0.0 {
+            bail!(?span = ident.span(), format!("{ident}: acceleration must be positive"), ?2);
+        }
+        acceleration_arms.push(quote! {
+            Self::#ident => #speed_path::from_knots(#acceleration)
+        });
+
+        if let Some(turn_rate) = turn_rate {
+            if turn_rate <= 0.0 || turn_rate >= 180.0 {
+                bail!(?span = ident.span(), format!("{ident}: turn_rate must be within (0, 180) degrees"), ?2);
+            }
+            turn_arms.push(quote! {
+                Self::#ident => #radian_path::from_deg(#turn_rate)
+            });
+        } else {
+            turn_arms.push(quote! {
+                Self::#ident => #default_turn_path
+            });
+        }
+
+        let dive_speed = dive_speed.unwrap_or(DEFAULT_DIVE_SPEED);
+        if dive_speed <= 0.0 {
+            bail!(?span = ident.span(), format!("{ident}: dive_speed must be positive"), ?2);
+        }
+        dive_arms.push(quote! {
+            Self::#ident => #speed_path::from_raw(#dive_speed)
+        });
+
+        if let Some(max_depth) = max_depth {
+            if max_depth <= 0.0 {
+                bail!(?span = ident.span(), format!("{ident}: max_depth must be positive"), ?2);
+            }
+            let const_name = Ident::new(&format!("_ASSERT_MAX_DEPTH_{ident}"), Span::call_site());
+            const_asserts.push(quote! {
+                const #const_name: () = assert!(#max_depth <= -#floor_path.0, "max_depth deeper than the ocean floor");
+            });
+            depth_arms.push(quote! {
+                Self::#ident => #z_index_path(-#max_depth)
+            });
+        } else {
+            depth_arms.push(quote! {
+                Self::#ident => #floor_path
+            });
+        }
+
+        let reverse_ratio = reverse_ratio.unwrap_or(DEFAULT_REVERSE_RATIO);
+        if reverse_ratio <= 0.0 || reverse_ratio > 1.0 {
+            bail!(?span = ident.span(), format!("{ident}: reverse_ratio must be within (0, 1]"), ?2);
+        }
+        reverse_arms.push(quote! {
+            Self::#ident => #reverse_ratio
+        });
+    }
+
+    let handling_impl = quote! {
+        /// speed gained or lost per tick
+        pub fn acceleration(&self) -> #speed_path {
+            match self {
+                #(#acceleration_arms),*
+            }
+        }
+        /// max turn per tick
+        pub fn max_turn(&self) -> #radian_path {
+            match self {
+                #(#turn_arms),*
+            }
+        }
+        /// ZIndex per tick, only meaningful for submarines
+        pub fn diving_speed(&self) -> #speed_path {
+            match self {
+                #(#dive_arms),*
+            }
+        }
+        /// lowest ZIndex reachable, only meaningful for submarines
+        pub fn max_depth(&self) -> #z_index_path {
+            match self {
+                #(#depth_arms),*
+            }
+        }
+        /// ratio of max speed reachable in reverse
+        pub fn reverse_ratio(&self) -> f32 {
+            match self {
+                #(#reverse_arms),*
+            }
+        }
+    };
+
+    (quote! { #(#const_asserts)* }, handling_impl)
+}
+
 /// derives the Size trait
 fn impl_size(variants: &Punctuated<Variant, Comma>, name: &Ident) -> TokenStream2 {
     let size_trait = absolute_path("primitives::Size");
@@ -749,7 +1028,7 @@ fn derive_weapon_type_inner(variants: Punctuated<Variant, Comma>, ident: Ident)
 
 fn derive_max_speed_inner(variants: Punctuated<Variant, Comma>, ident: Ident) -> TokenStream2 {
     let mut match_arms = vec![];
-    let speed_path = absolute_path("primitives::Speed");
+    let (speed_path, overridable_path) = (absolute_path("primitives::Speed"), absolute_path("definitions::Overridable"));
 
     for variant in variants {
         let ident = variant.ident;
@@ -782,9 +1061,9 @@ fn derive_max_speed_inner(variants: Punctuated<Variant, Comma>, ident: Ident) ->
         impl #ident {
             /// 0 speed represents no speed
             pub fn max_speed(&self) -> #speed_path {
-                #speed_path::from_knots(match self {
+                #speed_path::from_knots(<Self as #overridable_path>::max_speed_override(self).unwrap_or(match self {
                     #(#match_arms),*
-                })
+                }))
             }
         }
     }
@@ -793,6 +1072,7 @@ fn derive_max_speed_inner(variants: Punctuated<Variant, Comma>, ident: Ident) ->
 fn derive_weapon_reload_inner(variants: Punctuated<Variant, Comma>, ident: Ident) -> TokenStream2 {
     let mut match_arms = vec![];
     let duration_path = syn::parse_str::<Path>("::std::time::Duration").unwrap();
+    let overridable_path = absolute_path("definitions::Overridable");
 
     for variant in variants {
         let ident = variant.ident;
@@ -826,10 +1106,113 @@ fn derive_weapon_reload_inner(variants: Punctuated<Variant, Comma>, ident: Ident
     quote! {
         impl #ident {
             pub fn reload(&self) -> #duration_path {
-                #duration_path::from_secs_f32(match self {
+                #duration_path::from_secs_f32(<Self as #overridable_path>::reload_override(self).unwrap_or(match self {
                     #(#match_arms),*
-                })
+                }))
+            }
+        }
+    }
+}
+fn derive_weapon_expiry_inner(variants: Punctuated<Variant, Comma>, ident: Ident) -> TokenStream2 {
+    let mut range_arms = vec![];
+    let mut lifetime_arms = vec![];
+    let (duration_path, size_path) = (syn::parse_str::<Path>("::std::time::Duration").unwrap(), absolute_path("primitives::Size"));
+
+    for variant in &variants {
+        let variant_ident = &variant.ident;
+        let range = match parse_f32_attr(variant, "range") {
+            Ok(range) => range,
+            Err(e) => return e.to_compile_error(),
+        };
+        let lifetime = match parse_f32_attr(variant, "lifetime") {
+            Ok(lifetime) => lifetime,
+            Err(e) => return e.to_compile_error(),
+        };
+
+        match (range, lifetime) {
+            (Some(range), None) => {
+                if range <= 0.0 {
+                    bail!(?span = variant_ident.span(), "#[range] must be positive");
+                }
+                range_arms.push(quote! {
+                    Self::#variant_ident => ::std::option::Option::Some(#range * <Self as #size_path>::SIZE_TO_RENDER_MULTIPLIER)
+                });
+                lifetime_arms.push(quote! {
+                    Self::#variant_ident => ::std::option::Option::None
+                });
+            }
+            (None, Some(lifetime)) => {
+                if lifetime <= 0.0 {
+                    bail!(?span = variant_ident.span(), "#[lifetime] must be positive");
+                }
+                range_arms.push(quote! {
+                    Self::#variant_ident => ::std::option::Option::None
+                });
+                lifetime_arms.push(quote! {
+                    Self::#variant_ident => ::std::option::Option::Some(#duration_path::from_secs_f32(#lifetime))
+                });
             }
+            (Some(_), Some(_)) => bail!(?span = variant_ident.span(), "Specify only one of #[range] and #[lifetime]"),
+            (None, None) => bail!(?span = variant_ident.span(), "Expected #[range = <meters>] or #[lifetime = <seconds>]"),
         }
     }
-}
\ No newline at end of file
+
+    quote! {
+        impl #ident {
+            /// pixels travelled before expiring, None if limited by [`Self::lifetime`]
+            pub fn range(&self) -> ::std::option::Option<f32> {
+                match self {
+                    #(#range_arms),*
+                }
+            }
+            /// None if limited by [`Self::range`]
+            pub fn lifetime(&self) -> ::std::option::Option<#duration_path> {
+                match self {
+                    #(#lifetime_arms),*
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn test_parse_f32_attr() {
+        let parse = |variant: Variant| parse_f32_attr(&variant, "acceleration").map_err(|e| e.to_string());
+
+        assert_eq!(parse(parse_quote! { Foo }), Ok(None));
+        assert_eq!(parse(parse_quote! { #[level = 2] Foo }), Ok(None));
+        assert_eq!(parse(parse_quote! { #[acceleration = 2] Foo }), Ok(Some(2.0)));
+        assert_eq!(parse(parse_quote! { #[acceleration = 0.5] Foo }), Ok(Some(0.5)));
+        assert_eq!(parse(parse_quote! { #[acceleration = -1] Foo }), Ok(Some(-1.0)));
+        assert_eq!(parse(parse_quote! { #[acceleration = 100000] Foo }), Ok(Some(100000.0)));
+
+        assert_eq!(parse(parse_quote! { #[acceleration = 1] #[acceleration = 2] Foo }), Err("Duplicate #[acceleration]".to_owned()));
+        assert!(parse(parse_quote! { #[acceleration = "fast"] Foo }).is_err());
+        assert!(parse(parse_quote! { #[acceleration(1)] Foo }).is_err());
+        assert!(parse(parse_quote! { #[acceleration] Foo }).is_err());
+    }
+
+    #[test]
+    fn test_parse_mount() {
+        let parse = |call: ExprCall| parse_mount(&call).map(|(constructor, weapon_type, numbers)| {
+            (constructor.to_string(), weapon_type.to_string(), numbers)
+        });
+
+        assert_eq!(
+            parse(parse_quote! { turret(Shell, 45, 0, -90, 150.0, 0.6) }).unwrap(),
+            ("turret".to_owned(), "Shell".to_owned(), vec![45.0, 0.0, -90.0, 150.0, 0.6]),
+        );
+        assert_eq!(parse(parse_quote! { fixed(Torpedo, -5, 2, 90) }).unwrap().2, vec![-5.0, 2.0, 90.0]);
+
+        assert!(parse(parse_quote! { fixed(Torpedo, -5, 2) }).is_err());
+        assert!(parse(parse_quote! { turret(Shell, 45, 0, 0, 150) }).is_err());
+        assert!(parse(parse_quote! { cannon(Shell, 45, 0, 0) }).is_err());
+        assert!(parse(parse_quote! { fixed(45, 0, 0, 0) }).is_err());
+        assert!(parse(parse_quote! { fixed(Torpedo, x, 0, 0) }).is_err());
+    }
+}
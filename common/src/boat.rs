@@ -3,11 +3,7 @@ use lightyear::core::id::PeerId;
 use serde::{Deserialize, Serialize};
 use strum::{EnumCount, EnumIter, VariantArray};
 
-use crate::primitives::{Radian, Size};
-use crate::{
-    DEFAULT_MAX_TURN_DEG,
-    primitives::Speed
-};
+use crate::primitives::{Radian, Size, Speed};
 use macros::{BoatImpl, FetchSprite, MaxSpeed, Size};
 
 
@@ -24,11 +20,25 @@ pub enum Boat {
     #[max_speed = 31]
     #[level = 1]
     #[length = 35]
+    #[acceleration = 1]
+    #[turn_rate = 0.7]
+    #[mounts(
+        turret(Shell, 12, 0, 0, 150, 3),
+        turret(Shell, -12, 0, 180, 150, 3),
+        fixed(Torpedo, -3, 2.5, 0),
+        fixed(Torpedo, -3, -2.5, 0),
+    )]
     FairmileD,
     #[armanents(Type53, 2, default)]
     #[max_speed = 53]
     #[level = 1]
     #[length = 18.9]
+    #[acceleration = 1.6]
+    #[turn_rate = 0.9]
+    #[mounts(
+        fixed(Torpedo, -8, 1, 0),
+        fixed(Torpedo, -8, -1, 0),
+    )]
     G5,
     #[armanents(Type53, 2, default)]
     #[armanents(Shell_57x441Mmr, 2)]
@@ -36,41 +46,311 @@ pub enum Boat {
     #[max_speed = 43.9]
     #[level = 1]
     #[length = 25.4]
+    #[acceleration = 1.4]
+    #[turn_rate = 0.8]
+    #[mounts(
+        turret(Shell, 8, 0, 0, 150, 3),
+        fixed(Torpedo, -4, 2, 0),
+        fixed(Torpedo, -4, -2, 0),
+    )]
     Komar,
     #[armanents(None)]
     #[max_speed = 31.1]
     #[level = 1]
     #[length = 36.9]
+    #[acceleration = 0.8]
+    #[turn_rate = 0.6]
     Olympias,
     #[armanents(Mark18, 4, default)]
     #[max_speed = 40.9]
     #[level = 1]
     #[length = 23]
+    #[acceleration = 1.3]
+    #[turn_rate = 0.8]
+    #[mounts(
+        fixed(Torpedo, 3, 2.3, 0),
+        fixed(Torpedo, 3, -2.3, 0),
+        fixed(Torpedo, -5, 2.3, 0),
+        fixed(Torpedo, -5, -2.3, 0),
+    )]
     Pt34,
+    #[armanents(Mark18, 5, default)]
+    #[armanents(Shell_57x441Mmr, 1)]
+    #[max_speed = 17.6]
+    #[level = 2]
+    #[length =  67.1]
+    #[acceleration = 0.5]
+    #[turn_rate = 0.4]
+    #[dive_speed = 0.003]
+    #[max_depth = 0.25]
+    #[mounts(
+        turret(Shell, 10, 0, 0, 150, 2),
+        fixed(Torpedo, 31, 1, 0),
+        fixed(Torpedo, 31, -1, 0),
+        fixed(Torpedo, -31, 0, 180),
+    )]
+    TypeViic,
     #[armanents(Mark18, 4, default)]
     #[armanents(Shell_127x680Mmr, 3)]
     #[armanents(Mark9, 4)]
     #[max_speed = 36]
     #[level = 2]
     #[length = 85.3]
+    #[acceleration = 0.9]
+    #[turn_rate = 0.5]
+    #[mounts(
+        turret(Shell, 30, 0, 0, 150, 1.5),
+        turret(Shell, -5, 0, 180, 150, 1.5),
+        turret(Shell, -33, 0, 180, 150, 1.5),
+        fixed(Torpedo, -15, 0, 90),
+        fixed(Torpedo, -15, 0, -90),
+    )]
     Momi,
-    #[armanents(Mark18, 5, default)]
-    #[armanents(Shell_57x441Mmr, 1)]
-    #[max_speed = 17.6]
-    #[level = 2]
-    #[length =  67.1]
-    TypeViic,
     #[armanents(Of45, 9, default)]
     #[armanents(Shell_25x129Mmr, 2)]
     #[max_speed = 55]
     #[level = 2]
     #[length = 57]
+    #[acceleration = 1.5]
+    #[turn_rate = 0.6]
+    #[reverse_ratio = 0.3]
+    #[mounts(
+        turret(Shell, 15, 6, 45, 120, 3),
+        turret(Shell, 15, -6, -45, 120, 3),
+    )]
     Zubr,
+    #[armanents(P15, 4, default)]
+    #[armanents(Shell_25x129Mmr, 2)]
+    #[max_speed = 40]
+    #[level = 2]
+    #[length = 38.6]
+    #[acceleration = 1.4]
+    #[turn_rate = 0.8]
+    #[mounts(
+        turret(Shell, 12, 0, 0, 150, 3),
+        turret(Shell, -15, 0, 180, 150, 3),
+    )]
+    Osa,
+    #[armanents(Type53, 6, default)]
+    #[max_speed = 17]
+    #[level = 3]
+    #[length = 98.9]
+    #[acceleration = 0.5]
+    #[turn_rate = 0.4]
+    #[dive_speed = 0.003]
+    #[max_depth = 0.3]
+    #[mounts(
+        fixed(Torpedo, 45, 1.5, 0),
+        fixed(Torpedo, 45, -1.5, 0),
+    )]
+    Golf,
     #[armanents(Shell_300x1400Mmr, 8, default)]
     #[max_speed = 19.4]
     #[level = 3]
     #[length = 138.4]
+    #[acceleration = 0.4]
+    #[turn_rate = 0.25]
+    #[reverse_ratio = 0.4]
+    #[mounts(
+        turret(Shell, 45, 0, 0, 150, 0.6),
+        turret(Shell, 5, 8, 90, 80, 0.6),
+        turret(Shell, -5, -8, -90, 80, 0.6),
+        turret(Shell, -45, 0, 180, 150, 0.6),
+    )]
     Espana,
+    #[armanents(Mark18, 5, default)]
+    #[armanents(Shell_127x680Mmr, 5)]
+    #[armanents(Mark9, 6)]
+    #[max_speed = 36.5]
+    #[level = 3]
+    #[length = 114.8]
+    #[acceleration = 0.8]
+    #[turn_rate = 0.5]
+    #[mounts(
+        turret(Shell, 38, 0, 0, 150, 1.5),
+        turret(Shell, 28, 0, 0, 150, 1.5),
+        turret(Shell, -28, 0, 180, 150, 1.5),
+        turret(Shell, -38, 0, 180, 150, 1.5),
+        turret(Shell, -48, 0, 180, 150, 1.5),
+        fixed(Torpedo, -5, 2, 90),
+        fixed(Torpedo, -5, -2, -90),
+    )]
+    Fletcher,
+    #[armanents(Harpoon, 8, default)]
+    #[armanents(Shell_76x636Mmr, 1)]
+    #[max_speed = 38]
+    #[level = 3]
+    #[length = 60.4]
+    #[acceleration = 1.2]
+    #[turn_rate = 0.7]
+    #[mounts(
+        turret(Shell, 20, 0, 0, 150, 2),
+    )]
+    TuoChiang,
+    #[armanents(Mark48, 6, default)]
+    #[armanents(Harpoon, 2)]
+    #[max_speed = 17]
+    #[level = 4]
+    #[length = 90]
+    #[acceleration = 0.6]
+    #[turn_rate = 0.5]
+    #[dive_speed = 0.0035]
+    #[max_depth = 0.3]
+    #[mounts(
+        fixed(Torpedo, 41, 1.5, 0),
+        fixed(Torpedo, 41, -1.5, 0),
+    )]
+    Oberon,
+    #[armanents(Shell_127x680Mmr, 2, default)]
+    #[armanents(Harpoon, 4)]
+    #[armanents(Mark9, 4)]
+    #[max_speed = 28]
+    #[level = 4]
+    #[length = 113.4]
+    #[acceleration = 0.7]
+    #[turn_rate = 0.45]
+    #[mounts(
+        turret(Shell, 35, 0, 0, 150, 1.5),
+    )]
+    Leander,
+    #[armanents(Nsm, 8, default)]
+    #[armanents(Shell_76x636Mmr, 1)]
+    #[max_speed = 45]
+    #[level = 4]
+    #[length = 47.5]
+    #[acceleration = 1.5]
+    #[turn_rate = 0.8]
+    #[mounts(
+        turret(Shell, 14, 0, 0, 150, 2),
+    )]
+    Skjold,
+    #[armanents(Mark48, 6, default)]
+    #[max_speed = 33]
+    #[level = 5]
+    #[length = 76.7]
+    #[acceleration = 0.9]
+    #[turn_rate = 0.6]
+    #[dive_speed = 0.005]
+    #[max_depth = 0.35]
+    #[mounts(
+        fixed(Torpedo, 35, 1.5, 0),
+        fixed(Torpedo, 35, -1.5, 0),
+    )]
+    Skipjack,
+    #[armanents(Rbs15, 8, default)]
+    #[armanents(Tau2000, 4)]
+    #[armanents(Shell_57x441Mmr, 1)]
+    #[max_speed = 35]
+    #[level = 5]
+    #[length = 72.6]
+    #[acceleration = 1.3]
+    #[turn_rate = 0.7]
+    #[mounts(
+        turret(Shell, 22, 0, 0, 150, 2),
+        fixed(Torpedo, -10, 3, 90),
+        fixed(Torpedo, -10, -3, -90),
+    )]
+    Visby,
+    #[armanents(Kalibr, 8, default)]
+    #[armanents(Shell_76x636Mmr, 1)]
+    #[max_speed = 25]
+    #[level = 5]
+    #[length = 62]
+    #[acceleration = 1]
+    #[turn_rate = 0.7]
+    #[mounts(
+        turret(Shell, 20, 0, 0, 150, 2),
+    )]
+    Buyan,
+    #[armanents(Dm2A4, 6, default)]
+    #[armanents(Idas, 4)]
+    #[max_speed = 20]
+    #[level = 6]
+    #[length = 56]
+    #[acceleration = 0.8]
+    #[turn_rate = 0.7]
+    #[dive_speed = 0.004]
+    #[max_depth = 0.35]
+    #[mounts(
+        fixed(Torpedo, 25, 1.2, 0),
+        fixed(Torpedo, 25, -1.2, 0),
+    )]
+    Type212A,
+    #[armanents(Nsm, 8, default)]
+    #[armanents(Rim116, 4)]
+    #[armanents(Shell_57x441Mmr, 1)]
+    #[armanents(Mark54, 2)]
+    #[max_speed = 47]
+    #[level = 6]
+    #[length = 118]
+    #[acceleration = 1.2]
+    #[turn_rate = 0.6]
+    #[mounts(
+        turret(Shell, 38, 0, 0, 150, 2),
+        fixed(Torpedo, -20, 5, 90),
+        fixed(Torpedo, -20, -5, -90),
+    )]
+    Freedom,
+    #[armanents(BrahMos, 8, default)]
+    #[armanents(Barak8, 8)]
+    #[armanents(Shell_76x636Mmr, 1)]
+    #[armanents(Set65, 4)]
+    #[max_speed = 30]
+    #[level = 6]
+    #[length = 163]
+    #[acceleration = 0.7]
+    #[turn_rate = 0.4]
+    #[mounts(
+        turret(Shell, 60, 0, 0, 150, 2),
+        fixed(Torpedo, -10, 6, 90),
+        fixed(Torpedo, -10, -6, -90),
+    )]
+    Kolkata,
+    #[armanents(Set65, 6, default)]
+    #[armanents(Type53, 4)]
+    #[armanents(Vodopad, 2)]
+    #[max_speed = 35]
+    #[level = 7]
+    #[length = 113.3]
+    #[acceleration = 0.8]
+    #[turn_rate = 0.45]
+    #[dive_speed = 0.005]
+    #[max_depth = 0.4]
+    #[mounts(
+        fixed(Torpedo, 52, 2, 0),
+        fixed(Torpedo, 52, -2, 0),
+    )]
+    Akula,
+    #[armanents(Harpoon, 8, default)]
+    #[armanents(Tomahawk, 4)]
+    #[armanents(Essm, 8)]
+    #[armanents(Asroc, 4)]
+    #[armanents(Mark54, 6)]
+    #[armanents(Shell_127x680Mmr, 1)]
+    #[max_speed = 30]
+    #[level = 7]
+    #[length = 155]
+    #[acceleration = 0.7]
+    #[turn_rate = 0.4]
+    #[mounts(
+        turret(Shell, 55, 0, 0, 150, 1.5),
+        fixed(Torpedo, -25, 6, 90),
+        fixed(Torpedo, -25, -6, -90),
+    )]
+    ArleighBurke,
+    #[armanents(Shell_380x1700Mmr, 8, default)]
+    #[max_speed = 30]
+    #[level = 7]
+    #[length = 251]
+    #[acceleration = 0.4]
+    #[turn_rate = 0.2]
+    #[mounts(
+        turret(Shell, 80, 0, 0, 145, 0.4),
+        turret(Shell, 62, 0, 0, 145, 0.4),
+        turret(Shell, -62, 0, 180, 145, 0.4),
+        turret(Shell, -80, 0, 180, 145, 0.4),
+    )]
+    Bismarck,
     #[armanents(Set65, 4, default)]  // or maybe 6?
     #[armanents(BrahMos, 4)]
     #[armanents(Vodopad, 4)]
@@ -79,7 +359,130 @@ pub enum Boat {
     #[max_speed = 35]
     #[level = 8]
     #[length = 130]
+    #[acceleration = 0.8]
+    #[turn_rate = 0.4]
+    #[dive_speed = 0.004]
+    #[max_depth = 0.4]
+    #[mounts(
+        fixed(Torpedo, 25, 4, 15),
+        fixed(Torpedo, 25, -4, -15),
+    )]
     Yasen,
+    #[armanents(Yj18, 8, default)]
+    #[armanents(Hq9, 8)]
+    #[armanents(Shell_130x720Mmr, 1)]
+    #[armanents(Mark54, 6)]
+    #[max_speed = 30]
+    #[level = 8]
+    #[length = 180]
+    #[acceleration = 0.6]
+    #[turn_rate = 0.35]
+    #[mounts(
+        turret(Shell, 65, 0, 0, 150, 1.5),
+        fixed(Torpedo, -30, 7, 90),
+        fixed(Torpedo, -30, -7, -90),
+    )]
+    Type055,
+    #[armanents(P700, 8, default)]
+    #[armanents(S300, 8)]
+    #[armanents(Shell_130x720Mmr, 1)]
+    #[armanents(Type53, 4)]
+    #[max_speed = 32]
+    #[level = 8]
+    #[length = 186.4]
+    #[acceleration = 0.55]
+    #[turn_rate = 0.3]
+    #[mounts(
+        turret(Shell, -70, 0, 180, 150, 1.5),
+        fixed(Torpedo, -40, 7, 90),
+        fixed(Torpedo, -40, -7, -90),
+    )]
+    Moskva,
+    #[armanents(Shell_406x1800Mmr, 9, default)]
+    #[armanents(Tomahawk, 8)]
+    #[armanents(Harpoon, 8)]
+    #[max_speed = 33]
+    #[level = 9]
+    #[length = 270.4]
+    #[acceleration = 0.4]
+    #[turn_rate = 0.2]
+    #[mounts(
+        turret(Shell, 75, 0, 0, 150, 0.4),
+        turret(Shell, 55, 0, 0, 150, 0.4),
+        turret(Shell, -80, 0, 180, 150, 0.4),
+    )]
+    Iowa,
+    #[armanents(P700, 10, default)]
+    #[armanents(S300, 8)]
+    #[armanents(Shell_130x720Mmr, 1)]
+    #[armanents(Vodopad, 4)]
+    #[max_speed = 32]
+    #[level = 9]
+    #[length = 252]
+    #[acceleration = 0.5]
+    #[turn_rate = 0.25]
+    #[mounts(
+        turret(Shell, -100, 0, 180, 150, 1.5),
+    )]
+    Kirov,
+    #[armanents(Mark48, 8, default)]
+    #[armanents(Tomahawk, 4)]
+    #[armanents(Harpoon, 2)]
+    #[max_speed = 35]
+    #[level = 9]
+    #[length = 107.6]
+    #[acceleration = 0.8]
+    #[turn_rate = 0.45]
+    #[dive_speed = 0.005]
+    #[max_depth = 0.4]
+    #[mounts(
+        fixed(Torpedo, 48, 2, 0),
+        fixed(Torpedo, 48, -2, 0),
+    )]
+    Seawolf,
+    #[armanents(Shell_458x1980Mmr, 9, default)]
+    #[armanents(Shell_127x680Mmr, 2)]
+    #[max_speed = 27]
+    #[level = 10]
+    #[length = 263]
+    #[acceleration = 0.35]
+    #[turn_rate = 0.18]
+    #[mounts(
+        turret(Shell, 70, 0, 0, 150, 0.3),
+        turret(Shell, 52, 0, 0, 150, 0.3),
+        turret(Shell, -75, 0, 180, 150, 0.3),
+        turret(Shell, 0, 12, 90, 80, 1.5),
+        turret(Shell, 0, -12, -90, 80, 1.5),
+    )]
+    Yamato,
+    #[armanents(Tomahawk, 8, default)]
+    #[armanents(Essm, 8)]
+    #[armanents(Asroc, 4)]
+    #[armanents(Shell_57x441Mmr, 2)]
+    #[max_speed = 30]
+    #[level = 10]
+    #[length = 190]
+    #[acceleration = 0.6]
+    #[turn_rate = 0.3]
+    #[mounts(
+        turret(Shell, 50, 0, 0, 150, 2),
+        turret(Shell, -60, 0, 180, 150, 2),
+    )]
+    Zumwalt,
+    #[armanents(Mark48, 4, default)]
+    #[armanents(Tomahawk, 12)]
+    #[max_speed = 25]
+    #[level = 10]
+    #[length = 170]
+    #[acceleration = 0.5]
+    #[turn_rate = 0.3]
+    #[dive_speed = 0.0035]
+    #[max_depth = 0.4]
+    #[mounts(
+        fixed(Torpedo, 78, 2, 0),
+        fixed(Torpedo, 78, -2, 0),
+    )]
+    Ohio,
 }
 
 #[derive(Debug, Component)]
@@ -105,34 +508,58 @@ impl Boat {
             Self::Komar => SurfaceShip,
             Self::Olympias => SurfaceShip, // hmm
             Self::Pt34 => SurfaceShip,
-            Self::Momi => SurfaceShip,
             Self::TypeViic => Submarine,
+            Self::Momi => SurfaceShip,
             Self::Zubr => HoverCraft,
+            Self::Osa => SurfaceShip,
+            Self::Golf => Submarine,
             Self::Espana => SurfaceShip,
-            Self::Yasen => Submarine
+            Self::Fletcher => SurfaceShip,
+            Self::TuoChiang => SurfaceShip,
+            Self::Oberon => Submarine,
+            Self::Leander => SurfaceShip,
+            Self::Skjold => SurfaceShip,
+            Self::Skipjack => Submarine,
+            Self::Visby => SurfaceShip,
+            Self::Buyan => SurfaceShip,
+            Self::Type212A => Submarine,
+            Self::Freedom => SurfaceShip,
+            Self::Kolkata => SurfaceShip,
+            Self::Akula => Submarine,
+            Self::ArleighBurke => SurfaceShip,
+            Self::Bismarck => SurfaceShip,
+            Self::Seawolf => Submarine,
+            Self::Yasen => Submarine,
+            Self::Type055 => SurfaceShip,
+            Self::Moskva => SurfaceShip,
+            Self::Iowa => SurfaceShip,
+            Self::Kirov => SurfaceShip,
+            Self::Yamato => SurfaceShip,
+            Self::Zumwalt => SurfaceShip,
+            Self::Ohio => Submarine,
         }
     }
     pub fn rev_max_speed(&self) -> Speed {
-        self.max_speed() * 0.6
-    }
-    pub fn diving_speed(&self) -> Speed {
-        if self.sub_kind() == SubKind::Submarine {
-            Speed::from_raw(0.004)
-        } else {
-            unreachable!()
-        }
+        self.max_speed() * self.reverse_ratio()
     }
-    pub fn acceleration(&self) -> Speed {
-        Speed::from_knots(1.0)
-    }
-    /// max turn in degrees
-    pub fn max_turn(&self) -> Radian {
-        DEFAULT_MAX_TURN_DEG
+    /// scales with length in meters so bigger boats survive more hits
+    pub fn max_health(&self) -> f32 {
+        self.size().x
     }
     /// radius in pixels (length)
     pub fn radius(&self) -> f32 {
         self.render_size().x / 2.0
     }
+    /// where depth charges are dropped, in pixels
+    pub fn stern(&self, position: Vec2, rotation: Radian) -> Vec2 {
+        position - rotation.to_vec() * self.radius()
+    }
+    /// pixels from `point` to the line between the bow and the stern, used for splash damage
+    pub fn keel_distance(&self, position: Vec2, rotation: Radian, point: Vec2) -> f32 {
+        let half_keel = rotation.to_vec() * self.radius();
+        let along = (point - position).dot(half_keel) / half_keel.length_squared();
+        point.distance(position + half_keel * along.clamp(-1.0, 1.0))
+    }
     /// should use this function or code will break
     pub fn circle_hud_radius(&self) -> f32 {
         crate::util::add_circle_hud(self.radius())
@@ -144,6 +571,12 @@ impl Boat {
 #[derive(Debug, Component)]
 pub struct BoatClientId(pub PeerId);
 
+/// replicated marker on a [`Boat`] whose health reached zero
+///
+/// sinking boats can't move, fire, collect points or be hit, and are despawned by the server shortly after
+#[derive(Component, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Sinking;
+
 #[derive(Debug, Component)]
 pub struct BoatReversePositive;
 
@@ -83,8 +83,7 @@ pub fn square_does_not_intersects(
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 /// sat_collision performs continuous rectangle-based separating axis theorem collision.
-#[allow(dead_code)]
-pub(crate) fn sat_collision(
+pub fn sat_collision(
     mut transform: CustomTransform,
     mut dimensions: Vec2,
     radius: f32,
@@ -135,7 +134,6 @@ pub(crate) fn sat_collision(
 }
 
 /// sat_collision_half performs half an SAT test (checks angles of one of two rectangles).
-#[allow(dead_code)]
 fn sat_collision_half(
     position: Vec2,
     other_position: Vec2,
@@ -0,0 +1,249 @@
+//! optional data-driven overrides of [`Boat`] and [`Weapon`] stats
+//!
+//! the derives (`MaxSpeed`, `Reload`, `BoatImpl`) consult the active [`Definitions`] through [`Overridable`]
+//! before falling back to the compiled attributes, so balance changes don't need a rebuild.
+//!
+//! the server loads the file on start-up and sends it to every client on connect
+//!
+//! ### Example
+//! ```json
+//! {
+//!     "version": 1,
+//!     "boats": {
+//!         "Espana": { "max_speed": 21.0, "armanents": [["Shell_300x1400Mmr", 6], ["Mark9", 2]] }
+//!     },
+//!     "weapons": {
+//!         "Mark18": { "reload": 6.5 }
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use strum::EnumCount;
+
+use crate::primitives::Level;
+use crate::{Boat, Weapon};
+
+/// bumped on breaking changes to the file format
+pub const DEFINITIONS_VERSION: u32 = 1;
+
+/// set once and never changed, so lookups don't need a lock
+static ACTIVE: OnceLock<Definitions> = OnceLock::new();
+
+/// every field is optional, unspecified fields use the compiled values
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Definitions {
+    pub version: u32,
+    #[serde(default)]
+    pub boats: HashMap<Boat, BoatDefinition>,
+    #[serde(default)]
+    pub weapons: HashMap<Weapon, WeaponDefinition>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BoatDefinition {
+    /// knots
+    pub max_speed: Option<f32>,
+    pub level: Option<u8>,
+    /// in order, with the count of each weapon
+    pub armanents: Option<Vec<(Weapon, u16)>>,
+    /// defaults to the first of `armanents` if those are overridden
+    pub default_weapon: Option<Weapon>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WeaponDefinition {
+    /// knots
+    pub max_speed: Option<f32>,
+    /// seconds
+    pub reload: Option<f32>,
+}
+
+#[derive(Debug)]
+pub enum DefinitionsError {
+    Io(std::io::Error),
+    /// includes unknown boat/weapon names
+    Parse(serde_json::Error),
+    Version { found: u32 },
+    Invalid(String),
+}
+
+impl fmt::Display for DefinitionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "couldn't read definitions: {e}"),
+            Self::Parse(e) => write!(f, "couldn't parse definitions: {e}"),
+            Self::Version { found } => write!(f, "definitions version {found} is not supported, expected {DEFINITIONS_VERSION}"),
+            Self::Invalid(reason) => write!(f, "invalid definitions: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for DefinitionsError {}
+
+impl Definitions {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DefinitionsError> {
+        let text = std::fs::read_to_string(path).map_err(DefinitionsError::Io)?;
+        Self::parse(&text)
+    }
+    /// parses and [`validate`](Self::validate)s
+    pub fn parse(text: &str) -> Result<Self, DefinitionsError> {
+        let definitions: Self = serde_json::from_str(text).map_err(DefinitionsError::Parse)?;
+        definitions.validate()?;
+        Ok(definitions)
+    }
+    /// checks values the enums can't, against the compiled stats
+    pub fn validate(&self) -> Result<(), DefinitionsError> {
+        use DefinitionsError::Invalid;
+
+        if self.version != DEFINITIONS_VERSION {
+            return Err(DefinitionsError::Version { found: self.version });
+        }
+        for (boat, definition) in &self.boats {
+            if let Some(max_speed) = definition.max_speed
+                && (max_speed.is_nan() || max_speed <= 0.0)
+            {
+                return Err(Invalid(format!("{boat:?} max_speed must be positive")));
+            }
+            if let Some(level) = definition.level
+                && !(1..=Level::COUNT as u8).contains(&level)
+            {
+                return Err(Invalid(format!("{boat:?} level must be within 1..={}", Level::COUNT)));
+            }
+            if let Some(armanents) = &definition.armanents {
+                if armanents.iter().any(|(_, count)| *count == 0) {
+                    return Err(Invalid(format!("{boat:?} has an armanent with a count of 0")));
+                }
+                if armanents.iter().enumerate().any(|(i, (weapon, _))| armanents[..i].iter().any(|(w, _)| w == weapon)) {
+                    return Err(Invalid(format!("{boat:?} has duplicate armanents")));
+                }
+            }
+            if let Some(default) = definition.default_weapon {
+                let in_armanents = match &definition.armanents {
+                    Some(armanents) => armanents.iter().any(|(weapon, _)| *weapon == default),
+                    None => boat.armanents().get(&default).is_some(),
+                };
+                if !in_armanents {
+                    return Err(Invalid(format!("{boat:?}'s default weapon {default:?} is not one of its armanents")));
+                }
+            }
+        }
+        for (weapon, definition) in &self.weapons {
+            if let Some(max_speed) = definition.max_speed
+                && (max_speed.is_nan() || max_speed < 0.0)
+            {
+                return Err(Invalid(format!("{weapon:?} max_speed must not be negative")));
+            }
+            if let Some(reload) = definition.reload
+                && (reload.is_nan() || reload <= 0.0)
+            {
+                return Err(Invalid(format!("{weapon:?} reload must be positive")));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// sets the overrides used by every [`Boat`] and [`Weapon`] method, once
+///
+/// gives `definitions` back if some were already set
+pub fn set_active(definitions: Definitions) -> Result<(), Definitions> {
+    ACTIVE.set(definitions)
+}
+
+/// the applied overrides, if any
+pub fn active() -> Option<&'static Definitions> {
+    ACTIVE.get()
+}
+
+fn with_active<T>(f: impl FnOnce(&'static Definitions) -> Option<T>) -> Option<T> {
+    ACTIVE.get().and_then(f)
+}
+
+/// implemented for enums whose derived stats can be overridden by [`Definitions`]
+///
+/// all `None` means use the compiled value
+pub trait Overridable {
+    /// knots
+    fn max_speed_override(&self) -> Option<f32> {
+        None
+    }
+    /// seconds
+    fn reload_override(&self) -> Option<f32> {
+        None
+    }
+    fn level_override(&self) -> Option<u8> {
+        None
+    }
+    fn armanents_override(&self) -> Option<&'static [(Weapon, u16)]> {
+        None
+    }
+    /// `Some(None)` if overridden to have no weapons
+    fn default_weapon_override(&self) -> Option<Option<Weapon>> {
+        None
+    }
+}
+
+impl Overridable for Boat {
+    fn max_speed_override(&self) -> Option<f32> {
+        with_active(|d| d.boats.get(self)?.max_speed)
+    }
+    fn level_override(&self) -> Option<u8> {
+        with_active(|d| d.boats.get(self)?.level)
+    }
+    fn armanents_override(&self) -> Option<&'static [(Weapon, u16)]> {
+        with_active(|d| d.boats.get(self)?.armanents.as_deref())
+    }
+    fn default_weapon_override(&self) -> Option<Option<Weapon>> {
+        with_active(|d| {
+            let definition = d.boats.get(self)?;
+            match (&definition.armanents, definition.default_weapon) {
+                (_, Some(default)) => Some(Some(default)),
+                (Some(armanents), None) => Some(armanents.first().map(|(weapon, _)| *weapon)),
+                (None, None) => None,
+            }
+        })
+    }
+}
+
+impl Overridable for Weapon {
+    fn max_speed_override(&self) -> Option<f32> {
+        with_active(|d| d.weapons.get(self)?.max_speed)
+    }
+    fn reload_override(&self) -> Option<f32> {
+        with_active(|d| d.weapons.get(self)?.reload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_definitions() {
+        let definitions = Definitions::parse(r#"{
+            "version": 1,
+            "boats": { "Espana": { "max_speed": 21.0, "armanents": [["Shell_300x1400Mmr", 6], ["Mark9", 2]] } },
+            "weapons": { "Mark18": { "reload": 6.5 } }
+        }"#).unwrap();
+        assert_eq!(definitions.boats[&Boat::Espana].armanents.as_ref().unwrap()[1], (Weapon::Mark9, 2));
+        assert_eq!(definitions.weapons[&Weapon::Mark18].reload, Some(6.5));
+
+        assert!(matches!(Definitions::parse(r#"{ "version": 0 }"#), Err(DefinitionsError::Version { found: 0 })));
+        assert!(matches!(Definitions::parse(r#"{ "version": 1, "boats": { "Titanic": {} } }"#), Err(DefinitionsError::Parse(_))));
+        assert!(matches!(Definitions::parse(r#"{ "version": 1, "boats": { "Yasen": { "level": 11 } } }"#), Err(DefinitionsError::Invalid(_))));
+        assert!(matches!(Definitions::parse(r#"{ "version": 1, "weapons": { "Mark18": { "reload": 0 } } }"#), Err(DefinitionsError::Invalid(_))));
+        assert!(matches!(
+            Definitions::parse(r#"{ "version": 1, "boats": { "Yasen": { "armanents": [["Mark18", 1]], "default_weapon": "Igla" } } }"#),
+            Err(DefinitionsError::Invalid(_))
+        ));
+    }
+}
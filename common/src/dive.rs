@@ -0,0 +1,68 @@
+//! depth of boats, simulated from [`DiveIntent`] by both sides like [`CustomTransform`](crate::primitives::CustomTransform) is from [`Move`](crate::protocol::Move)
+//!
+//! for client: local prediction
+//! for server: the only source of truth, the client never sends its [`ZIndex`]
+//!
+//! only submarines leave [`OCEAN_SURFACE`], any other hull (e.g. after upgrading from a submarine) is pushed back up
+
+use bevy::prelude::*;
+use lightyear::prelude::input::native::ActionState;
+
+use crate::boat::{Boat, Sinking, SubKind};
+use crate::primitives::ZIndex;
+use crate::protocol::DiveIntent;
+use crate::OCEAN_SURFACE;
+
+/// moves [`ZIndex`] towards [`DiveIntent`] by at most [`Boat::diving_speed`] every tick
+pub struct DivePlugin;
+
+impl Plugin for DivePlugin {
+    fn build(&self, app: &mut App) {
+        // `FixedUpdate` because inputs are tick-synced
+        #[cfg(feature = "client")]
+        app.add_systems(FixedUpdate, client::dive);
+        #[cfg(feature = "server")]
+        app.add_systems(FixedUpdate, server::dive);
+    }
+}
+
+#[cfg(feature = "server")]
+mod server {
+    use super::*;
+
+    pub fn dive(query: Query<(&ActionState<DiveIntent>, &mut ZIndex, &Boat), Without<Sinking>>) {
+        for (intent, mut depth, boat) in query {
+            if let Some(next) = next_depth(intent.0, *depth, boat) {
+                *depth = next;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "client")]
+mod client {
+    use super::*;
+    use lightyear::prelude::Controlled;
+
+    /// others' depth is replicated
+    pub fn dive(query: Single<(&ActionState<DiveIntent>, &mut ZIndex, &Boat), (With<Controlled>, Without<Sinking>)>) {
+        let (intent, mut depth, boat) = query.into_inner();
+        if let Some(next) = next_depth(intent.0, *depth, boat) {
+            *depth = next;
+        }
+    }
+}
+
+/// None if the boat stays where it is, to keep change detection quiet
+fn next_depth(intent: DiveIntent, depth: ZIndex, boat: &Boat) -> Option<ZIndex> {
+    let target = match (boat.sub_kind(), intent) {
+        (SubKind::Submarine, DiveIntent::Dive) => boat.max_depth(),
+        (SubKind::Submarine, DiveIntent::Surface) => OCEAN_SURFACE,
+        // still rises if upgraded to a submarine that can't go as deep
+        (SubKind::Submarine, DiveIntent::Hold) => ZIndex(depth.0.max(boat.max_depth().0)),
+        _ => OCEAN_SURFACE,
+    };
+    let step = boat.diving_speed().get_raw();
+    let next = depth.0 + (target.0 - depth.0).clamp(-step, step);
+    (next != depth.0).then_some(ZIndex(next))
+}
@@ -0,0 +1,87 @@
+//! uniform grid over the world for proximity queries, so the server doesn't compare everything with everything
+//!
+//! the server rebuilds one [`SpatialGrid`] per kind of entity (e.g. `SpatialGrid<Boat>`) every tick
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+/// entities of kind `T` bucketed by the cell their position falls into
+///
+/// the marker only tells the grids apart as resources
+#[derive(Resource, Debug)]
+pub struct SpatialGrid<T> {
+    /// pixels
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+    _kind: PhantomData<fn() -> T>,
+}
+
+impl<T> SpatialGrid<T> {
+    /// pixels, around the size of the screen
+    pub const DEFAULT_CELL_SIZE: f32 = 500.0;
+
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "cell size must be positive");
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            _kind: PhantomData,
+        }
+    }
+    /// keeps the allocated cells, the world doesn't change much from tick to tick
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+    pub fn insert(&mut self, entity: Entity, position: Vec2) {
+        self.cells.entry(self.cell(position)).or_default().push((entity, position));
+    }
+    /// (entity, position) of everything within `radius` pixels of `center`, in no particular order
+    pub fn within(&self, center: Vec2, radius: f32) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
+        let min = self.cell(center - radius);
+        let max = self.cell(center + radius);
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| ivec2(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |&(_, position)| position.distance_squared(center) <= radius * radius)
+    }
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+}
+
+impl<T> Default for SpatialGrid<T> {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CELL_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_within() {
+        let mut grid = SpatialGrid::<()>::new(10.0);
+        let positions = [vec2(0.0, 0.0), vec2(9.9, 9.9), vec2(-0.1, -0.1), vec2(25.0, 0.0), vec2(-14.0, 0.0)];
+        for (i, position) in positions.into_iter().enumerate() {
+            grid.insert(Entity::from_bits(i as u64), position);
+        }
+
+        let mut found: Vec<u64> = grid.within(Vec2::ZERO, 15.0).map(|(entity, _)| entity.to_bits()).collect();
+        found.sort();
+        assert_eq!(found, [0, 1, 2, 4]);
+
+        // across cell borders and negative cells
+        let found: Vec<u64> = grid.within(vec2(-10.0, 0.0), 5.0).map(|(entity, _)| entity.to_bits()).collect();
+        assert_eq!(found, [4]);
+
+        grid.clear();
+        assert_eq!(grid.within(Vec2::ZERO, 100.0).count(), 0);
+    }
+}
@@ -4,7 +4,10 @@ use crate::primitives::ZIndex;
 use crate::util::ip_addr;
 
 mod boat;
+mod dive;
 mod movement;
+mod sensor;
+mod turret;
 mod weapon;
 mod world;
 mod upgrade;
@@ -15,19 +18,28 @@ mod shaders;
 // TODO test these
 
 pub mod collision;
+pub mod definitions;
+pub mod grid;
 pub mod primitives;
 pub mod protocol;
 pub mod util;
-pub use movement::MovementPlugin;
+pub use movement::{MovementPlugin, Seeker, Spoofed, LaunchedBy, Domain, Travelled, Staging, Staged, Ballistic, Landed};
+#[cfg(feature = "server")]
+pub use movement::{Rammed, AllowLargerSpeed, SpeedViolation, SpeedViolations};
 #[cfg(feature = "server")]
 pub use upgrade::UpgradeSet;
 pub use upgrade::UpgradeEventCommonFinished;
 pub use upgrade::UpgradePlugin;
-pub use weapon::{Weapon, WeaponType};
-pub use boat::{Boat, SubKind, CircleHud, BoatClientId, BoatReverseNegative, BoatReversePositive};
+pub use weapon::{Weapon, WeaponType, DEPTH_CHARGE_SINK_SPEED, DEPTH_CHARGE_FUSE_RADIUS, DEPTH_CHARGE_BLAST_RADIUS, DEPTH_CHARGE_DEPTH_BAND, SONAR_DECOY_EMISSION, SHELL_SPLASH_RADIUS};
+pub use boat::{Boat, SubKind, CircleHud, BoatClientId, BoatReverseNegative, BoatReversePositive, Sinking};
 #[cfg(feature = "client")]
 pub use boat::BoatType;
+pub use dive::DivePlugin;
+pub use sensor::{Sensors, ACTIVE_SONAR_RANGE, ACTIVE_SONAR_REVEAL_RANGE, MAX_SENSOR_RANGE};
+pub use turret::{Mount, TurretPlugin, Turrets};
 pub use world::{WorldPlugin, WorldSize};
+#[cfg(feature = "server")]
+pub use world::WorldMin;
 
 pub use macros::BoatImpl;
 pub use macros;
@@ -61,6 +73,8 @@ const CLIENT_PORT: u16 = 8001;
 
 pub const TCP_ADDR: SocketAddr = ip_addr(Ipv4Addr::LOCALHOST, 9000);
 pub const TCP_WS_ADDR: &str = "ws://127.0.0.1:9000";
+/// response header of `/join` with the address to connect to, the server may not be where `/join` is
+pub const GAME_ADDR_HEADER: &str = "x-mk73-game-addr";
 
 const DEFAULT_MAX_TURN_DEG: crate::primitives::Radian = crate::primitives::Radian::from_deg(0.5);
 
@@ -9,16 +9,19 @@
 #![allow(clippy::type_complexity)]
 
 // note that we're passing owned vals everywhere which doesn't matter for types smaller than 64 bits
-use crate::boat::Boat;
-use crate::collision::out_of_bound_point;
-use crate::primitives::{CustomTransform, LastSpeed, NormalizeRadian, Radian, Size, Speed, TargetRotation, WrapRadian};
-use crate::protocol::{Move, Rotate};
+use std::time::Duration;
+
+use crate::boat::{Boat, Sinking};
+use crate::collision::{out_of_bound_point, sat_collision};
+use crate::primitives::{CustomTransform, LastSpeed, NormalizeRadian, Position, Radian, Size, Speed, TargetRotation, WrapRadian, ZIndex};
+use crate::protocol::{Move, OilRigTransform, Rotate};
 use crate::world::WorldSize;
 
-use bevy::prelude::*;
+use bevy::{ecs::entity::MapEntities, prelude::*};
+use serde::{Deserialize, Serialize};
 use lightyear::prelude::input::native::ActionState;
-use crate::{eq, Weapon};
-use crate::util::move_with_rotation;
+use crate::{eq, Weapon, WeaponType, SONAR_DECOY_EMISSION};
+use crate::util::{get_rotate_radian, move_with_rotation, seeded_unit};
 
 /// plugin to verify inputs and apply them to [`CustomTransform::rotation`] for `rotate`
 /// [`CustomTransform::speed`] and [`CustomTransform::position`] for `move`
@@ -39,9 +42,10 @@ impl Plugin for MovementPlugin {
     fn build(&self, app: &mut App) {
         // `FixedUpdate` because inputs are tick-synced
         #[cfg(feature = "client")]
-        app.add_systems(FixedUpdate, (client::rotate, client::move_));
+        app.add_systems(FixedUpdate, (client::rotate, client::move_, client::collide).chain());
         #[cfg(feature = "server")]
-        app.add_systems(FixedUpdate, (server::rotate, server::move_));
+        app.add_systems(FixedUpdate, (server::expire_speed_grace, server::rotate, server::move_, server::collide).chain())
+            .add_observer(server::allow_larger_speed);
         
         if self.move_weapon {
             app.add_plugins(WeaponMovementPlugin);
@@ -50,20 +54,198 @@ impl Plugin for MovementPlugin {
 }
 
 
+/// boats (or rigs) only collide if their depths are within this
+const COLLISION_DEPTH_PRECISION: f32 = 0.05;
+/// pixels pushed apart every tick while overlapping, on top of the closing speed
+const COLLISION_PUSH: f32 = 1.0;
+/// damage per knot of closing speed per meter of the other boat's length
+const RAMMING_DAMAGE_MULTIPLIER: f32 = 0.005;
+
+/// emitted on the server when a boat collides with something moving towards it
+///
+/// the server applies the damage to [`Health`](crate::primitives::Health)
+#[cfg(feature = "server")]
+#[derive(Debug, EntityEvent)]
+pub struct Rammed {
+    pub entity: Entity,
+    pub damage: f32,
+    pub reason: crate::protocol::DeathReason,
+}
+
+/// extra ticks on top of the deceleration before [`AllowLargerSpeed`] runs out
+#[cfg(feature = "server")]
+const SPEED_GRACE_TICKS: u32 = 2;
+
+/// inserted by the server after an upgrade leaves the boat faster than its new bounds, see [`validate_speed_cheating`]
+///
+/// speeds out of bounds aren't clamped until `deadline`, by which the boat is expected to have slowed down
+#[cfg(feature = "server")]
+#[derive(Debug, Component)]
+pub struct AllowLargerSpeed {
+    /// elapsed [`Time<Fixed>`]
+    pub deadline: Duration,
+}
+
+/// clamps at most this far apart belong to the same incident, see [`SpeedViolations`]
+#[cfg(feature = "server")]
+const SPEED_INCIDENT_GAP: Duration = Duration::from_secs(1);
+
+/// how many times a client's boats were caught out of their speed bounds, on the client entity (with `ClientOf`)
+///
+/// a boat held above its bounds is clamped every tick, so clamps within `SPEED_INCIDENT_GAP` of each other
+/// count as one incident. kept across respawns, dropped when the client disconnects
+#[cfg(feature = "server")]
+#[derive(Debug, Component)]
+pub struct SpeedViolations {
+    pub incidents: u32,
+    /// elapsed [`Time<Fixed>`] of the latest clamp
+    pub last: Duration,
+}
+
+/// emitted on the server when a boat's speed starts being clamped into its bounds while its client requested it
+///
+/// once per incident, `entity` is the client entity, so persistent offenders can be kicked
+#[cfg(feature = "server")]
+#[derive(Debug, EntityEvent)]
+pub struct SpeedViolation {
+    pub entity: Entity,
+    pub boat: Entity,
+    /// incidents including this one, see [`SpeedViolations`]
+    pub violations: u32,
+}
+
 #[cfg(feature = "server")]
 mod server {
     use super::*;
+    use crate::protocol::DeathReason;
+    use crate::UpgradeEventCommonFinished;
+    use lightyear::prelude::ControlledBy;
 
     pub fn rotate(
-        query: Query<(&ActionState<Rotate>, &mut CustomTransform, &Boat)>,
+        query: Query<(&ActionState<Rotate>, &mut CustomTransform, &Boat), Without<Sinking>>,
     ) {
         for (action, mut custom, boat) in query {
             super::rotate_inner(action, &mut custom, boat)
         }
     }
-    pub fn move_(query: Query<(&ActionState<Move>, &mut CustomTransform, &Boat)>, world_size: Res<WorldSize>) {
-        for (action, mut custom, boat) in query {
+    /// also clamps the speed into the boat's bounds unless it has [`AllowLargerSpeed`]
+    pub fn move_(
+        query: Query<(&ActionState<Move>, &mut CustomTransform, &Boat, Option<&ControlledBy>, Has<AllowLargerSpeed>, Entity), Without<Sinking>>,
+        mut violations: Query<&mut SpeedViolations>,
+        world_size: Res<WorldSize>,
+        time: Res<Time<Fixed>>,
+        mut commands: Commands,
+    ) {
+        for (action, mut custom, boat, controlled_by, grace, id) in query {
             super::move_inner(action, &mut custom, boat, &world_size);
+            if grace {
+                continue;
+            }
+            custom.speed = match validate_speed_cheating(&custom.speed, boat.max_speed(), boat.rev_max_speed()) {
+                SpeedValidity::Normal => continue,
+                SpeedValidity::PositiveErr => boat.max_speed(),
+                SpeedValidity::NegativeErr => -boat.rev_max_speed(),
+            };
+
+            // only count it against the client if it asked for the speed
+            let (Some(controlled_by), Some(_)) = (controlled_by, action.0.0) else {
+                continue;
+            };
+            let now = time.elapsed();
+            let incidents = match violations.get_mut(controlled_by.owner) {
+                Ok(mut violations) => {
+                    let ongoing = now.saturating_sub(violations.last) <= SPEED_INCIDENT_GAP;
+                    violations.last = now;
+                    if ongoing {
+                        continue;
+                    }
+                    violations.incidents += 1;
+                    violations.incidents
+                }
+                Err(_) => {
+                    commands.entity(controlled_by.owner).try_insert(SpeedViolations { incidents: 1, last: now });
+                    1
+                }
+            };
+            commands.trigger(SpeedViolation { entity: controlled_by.owner, boat: id, violations: incidents });
+        }
+    }
+    /// gives the boat until it could have slowed down to its new bounds, see [`AllowLargerSpeed`]
+    pub fn allow_larger_speed(
+        trigger: On<UpgradeEventCommonFinished>,
+        boats: Query<(&CustomTransform, &Boat)>,
+        time: Res<Time<Fixed>>,
+        mut commands: Commands,
+    ) {
+        let Ok((custom, boat)) = boats.get(trigger.entity) else {
+            return;
+        };
+        let excess = (custom.speed - boat.max_speed()).get_raw().max((-boat.rev_max_speed() - custom.speed).get_raw());
+        if excess <= 0.0 {
+            return;
+        }
+        let ticks = (excess / boat.acceleration().get_raw()).ceil() as u32 + SPEED_GRACE_TICKS;
+        debug!(?boat, ticks, "Allowing larger speed after upgrade");
+        commands.entity(trigger.entity).insert(AllowLargerSpeed {
+            deadline: time.elapsed() + time.timestep() * ticks,
+        });
+    }
+    /// any speed still out of bounds afterwards gets clamped by [`move_`]
+    pub fn expire_speed_grace(query: Query<(&AllowLargerSpeed, Entity)>, time: Res<Time<Fixed>>, mut commands: Commands) {
+        for (grace, id) in query {
+            if time.elapsed() >= grace.deadline {
+                commands.entity(id).remove::<AllowLargerSpeed>();
+            }
+        }
+    }
+    /// pushes both boats apart and damages both according to the other's length
+    pub fn collide(
+        mut boats: Query<(&mut CustomTransform, &Boat, &ZIndex, Entity), Without<Sinking>>,
+        rigs: Query<&OilRigTransform>,
+        mut commands: Commands,
+    ) {
+        let mut combinations = boats.iter_combinations_mut();
+        while let Some([
+            (mut custom, boat, depth, id),
+            (mut other_custom, other_boat, other_depth, other_id)
+        ]) = combinations.fetch_next() {
+            if !depth_compatible(*depth, *other_depth) {
+                continue;
+            }
+            let Some((direction, closing)) = detect_overlap(&custom, boat.render_size(), &other_custom, other_boat.render_size()) else {
+                continue;
+            };
+            push_apart(&mut custom, direction, closing);
+            push_apart(&mut other_custom, -direction, closing);
+
+            if closing > Speed::ZERO {
+                commands.trigger(Rammed {
+                    entity: id,
+                    damage: ramming_damage(closing, other_boat.size().x),
+                    reason: DeathReason::Rammed { killer: *other_boat },
+                });
+                commands.trigger(Rammed {
+                    entity: other_id,
+                    damage: ramming_damage(closing, boat.size().x),
+                    reason: DeathReason::Rammed { killer: *boat },
+                });
+            }
+        }
+
+        for (mut custom, boat, depth, id) in &mut boats {
+            for rig in &rigs {
+                let Some((direction, closing)) = detect_rig_overlap(&custom, boat.render_size(), *depth, rig) else {
+                    continue;
+                };
+                push_apart(&mut custom, direction, closing);
+                if closing > Speed::ZERO {
+                    commands.trigger(Rammed {
+                        entity: id,
+                        damage: ramming_damage(closing, rig.size().x),
+                        reason: DeathReason::OilRig,
+                    });
+                }
+            }
         }
     }
 }
@@ -74,15 +256,36 @@ mod client {
     use lightyear::prelude::Controlled;
 
     pub fn rotate(
-        query: Single<(&ActionState<Rotate>, &mut CustomTransform, &Boat), With<Controlled>>,
+        query: Single<(&ActionState<Rotate>, &mut CustomTransform, &Boat), (With<Controlled>, Without<Sinking>)>,
     ) {
         let (action, mut custom, boat) = query.into_inner();
         super::rotate_inner(action, &mut custom, boat)
     }
-    pub fn move_(query: Single<(&ActionState<Move>, &mut CustomTransform, &Boat), With<Controlled>>, world_size: Res<WorldSize>) {
+    pub fn move_(query: Single<(&ActionState<Move>, &mut CustomTransform, &Boat), (With<Controlled>, Without<Sinking>)>, world_size: Res<WorldSize>) {
         let (action, mut custom, boat) = query.into_inner();
         super::move_inner(action, &mut custom, boat, world_size.into_inner());
     }
+    /// only pushes the predicted boat, others are interpolated and damage is server-side
+    pub fn collide(
+        query: Single<(&mut CustomTransform, &Boat, &ZIndex), (With<Controlled>, Without<Sinking>)>,
+        others: Query<(&CustomTransform, &Boat, &ZIndex), (Without<Controlled>, Without<Sinking>)>,
+        rigs: Query<&OilRigTransform>,
+    ) {
+        let (mut custom, boat, depth) = query.into_inner();
+        for (other_custom, other_boat, other_depth) in others {
+            if !depth_compatible(*depth, *other_depth) {
+                continue;
+            }
+            if let Some((direction, closing)) = detect_overlap(&custom, boat.render_size(), other_custom, other_boat.render_size()) {
+                push_apart(&mut custom, direction, closing);
+            }
+        }
+        for rig in rigs {
+            if let Some((direction, closing)) = detect_rig_overlap(&custom, boat.render_size(), *depth, rig) {
+                push_apart(&mut custom, direction, closing);
+            }
+        }
+    }
 }
 
 fn rotate_inner(rotate_input: &ActionState<Rotate>, custom: &mut CustomTransform, boat: &Boat) {
@@ -104,14 +307,57 @@ fn move_inner(move_input: &ActionState<Move>, custom: &mut CustomTransform, boat
         custom.speed,
         boat.acceleration(),
     );
-
-    // if validate_speed_cheating(&target, boat.max_speed(), boat.rev_max_speed()) == SpeedValidity::Error {
-    //     warn!(?boat, target = target.get_knots());
-    //     return;
-    // }
+    // the server clamps it into the boat's bounds afterwards, see `server::move_`
     custom.speed = target;
 }
 
+/// a submerged boat passes under surface boats and rigs
+fn depth_compatible(depth: ZIndex, other_depth: ZIndex) -> bool {
+    (*depth - *other_depth).abs() < COLLISION_DEPTH_PRECISION
+}
+
+fn velocity(custom: &CustomTransform) -> Vec2 {
+    custom.rotation.to_vec() * custom.speed.get_raw()
+}
+
+/// if overlapping, returns the direction to push `custom` away from `other` and how fast they're closing in
+///
+/// sizes in pixels
+fn detect_overlap(custom: &CustomTransform, size: Vec2, other: &CustomTransform, other_size: Vec2) -> Option<(Vec2, Speed)> {
+    // not sweeping, boats are slow enough and the sweep doesn't handle reversing
+    let still = |custom: &CustomTransform| CustomTransform { speed: Speed::ZERO, ..*custom };
+    if !sat_collision(still(custom), size, size.length() / 2.0, still(other), other_size, other_size.length() / 2.0) {
+        return None;
+    }
+
+    let direction = (custom.position.0 - other.position.0).normalize_or(custom.rotation.to_vec());
+    let closing = (velocity(other) - velocity(custom)).dot(direction).max(0.0);
+    Some((direction, Speed::from_raw(closing)))
+}
+
+/// rigs are on the surface and never move
+fn detect_rig_overlap(custom: &CustomTransform, size: Vec2, depth: ZIndex, rig: &OilRigTransform) -> Option<(Vec2, Speed)> {
+    if !depth_compatible(depth, crate::OCEAN_SURFACE) {
+        return None;
+    }
+    let rig_custom = CustomTransform {
+        speed: Speed::ZERO,
+        position: Position(rig.position),
+        rotation: rig.rotation,
+    };
+    detect_overlap(custom, size, &rig_custom, rig.render_size())
+}
+
+fn push_apart(custom: &mut CustomTransform, direction: Vec2, closing: Speed) {
+    custom.position.0 += direction * (COLLISION_PUSH + closing.get_raw() / 2.0);
+    custom.speed = custom.speed * 0.5;
+}
+
+#[cfg(feature = "server")]
+fn ramming_damage(closing: Speed, other_length: f32) -> f32 {
+    closing.get_knots() * other_length * RAMMING_DAMAGE_MULTIPLIER
+}
+
 /// validates client input against max turning degree
 /// - `target`: `let Some(target) = rotate_input else { return }`
 fn validate_max_turn(target: &mut Radian, current_rotation: Radian, max_turn: Radian) {
@@ -143,36 +389,28 @@ fn validate_acceleration(
 // FIXME playerscore clearing to 0 on upgrade with points around sometimes (???????)
 
 #[derive(PartialEq)]
-#[allow(dead_code)]
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
 enum SpeedValidity {
     PositiveErr,
     NegativeErr,
     Normal
 }
 
-/* FIXME security vulneribility
-fix:
-when player upgrades, the server inserts AllowLargerSpeed if current speed is larger than target boat's bounds
-if that component exists, validate_speed_cheating is ignored
-AllowLargerSpeed contains the deadline at which the boat is expected to reach
-when deadline + 2 frames is reached, AllowLargerSpeed is removed, therefore
-any out-of-bound would be snapped back
-*/
 /// sanity check: speed upper + lower bound
-/// should be run after validating acceleration
+/// should be run after validating acceleration, the server ignores it while the boat has `AllowLargerSpeed`
 /// - `reverse_max_speed` assumes positive from [`Boat`]
 #[must_use = "Result may be a err value which should be handled"]
-#[allow(dead_code)]
+#[cfg_attr(not(feature = "server"), allow(dead_code))]
 fn validate_speed_cheating(target: &Speed, max_speed: Speed, reverse_max_speed: Speed) -> SpeedValidity {
     if *target > max_speed {
-        warn!(
+        trace!(
             "Got speed {} greater than max speed {}",
             target.get_knots(),
             max_speed.get_knots()
         );
         SpeedValidity::PositiveErr
     } else if *target < - reverse_max_speed {
-        warn!(
+        trace!(
             "Got speed {} lesser than reverse max speed {}",
             target.get_knots(),
             - reverse_max_speed.get_knots()
@@ -192,15 +430,219 @@ fn validate_speed_cheating(target: &Speed, max_speed: Speed, reverse_max_speed:
 /// - `TargetRotation`
 /// - `Weapon`
 /// - `LastSpeed`
+///
+/// guided weapons additionally have a [`Seeker`]
 struct WeaponMovementPlugin;
 
 impl Plugin for WeaponMovementPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(FixedUpdate, (rotate_weapon, move_weapon).chain());  // chain and FixedUpdate for minimal diff between server and cient
+        app.add_systems(FixedUpdate, (seek, rotate_weapon, move_weapon, sink_depth_charges, release_payloads, land_shells, despawn_expired_weapon).chain());  // chain and FixedUpdate for minimal diff between server and cient
         app.add_systems(FixedUpdate, despawn_weapon_out_of_bound);
     }
 }
 
+/// how far and how long a weapon has travelled, compared against [`Weapon::range`] and [`Weapon::lifetime`]
+///
+/// required by [`Weapon`], counted locally so both sides expire the weapon on the same tick
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Travelled {
+    /// pixels
+    distance: f32,
+    time: Duration,
+}
+
+impl Travelled {
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+    /// whether a sonar decoy this old still gives off its fake signature
+    pub fn decoy_emitting(&self) -> bool {
+        self.time < SONAR_DECOY_EMISSION
+    }
+    fn expired(&self, weapon: Weapon) -> bool {
+        weapon.range().is_some_and(|range| self.distance >= range)
+            || weapon.lifetime().is_some_and(|lifetime| self.time >= lifetime)
+    }
+}
+
+/// a shot lands up to this far either side of where it was aimed
+const SHELL_SPREAD: Radian = Radian::from_deg(1.5);
+/// a shot lands up to this fraction short or long of where it was aimed
+const SHELL_RANGE_SPREAD: f32 = 0.04;
+
+/// ticks after launch before a [`Seeker`] starts looking for targets
+const SEEKER_ARMING_TICKS: u16 = 30;
+/// half of the forward cone in which targets are acquired
+const SEEKER_HALF_ANGLE: Radian = Radian::from_deg(30.0);
+
+/// where something is, as far as seekers are concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Domain {
+    Air,
+    Surface,
+    Submerged,
+}
+
+impl Domain {
+    pub fn of_depth(depth: ZIndex) -> Self {
+        if depth_compatible(depth, crate::OCEAN_SURFACE) {
+            Self::Surface
+        } else {
+            Self::Submerged
+        }
+    }
+}
+
+/// the boat that fired a weapon, never targeted by the weapon's [`Seeker`] nor by the same boat's anti-air
+///
+/// replicated for others' weapons, a launcher the client can't see maps to nothing
+#[derive(Component, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaunchedBy(pub Entity);
+
+impl MapEntities for LaunchedBy {
+    fn map_entities<E: EntityMapper>(&mut self, entity_mapper: &mut E) {
+        self.0 = entity_mapper.get_mapped(self.0);
+    }
+}
+
+/// homing state of a guided weapon, simulated independently on the server and every client
+///
+/// acquires the nearest target inside a forward cone once armed, then overwrites [`TargetRotation`]
+/// each tick so [`Weapon::max_turn_radian`] still limits the turn
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Seeker {
+    arming_ticks: u16,
+    target: Option<Entity>,
+}
+
+impl Seeker {
+    /// None for unguided weapons
+    pub fn new(weapon: Weapon) -> Option<Self> {
+        weapon.weapon_type().seeker_range()?;
+        Some(Self {
+            arming_ticks: SEEKER_ARMING_TICKS,
+            target: None,
+        })
+    }
+    pub fn target(&self) -> Option<Entity> {
+        self.target
+    }
+    /// the distance and bearing to `target` if it's inside the cone of a seeker at `transform`
+    pub fn sight(weapon_type: WeaponType, transform: &Transform, target: Vec2) -> Option<(f32, Radian)> {
+        let range = weapon_type.seeker_range()?;
+        let position = transform.translation.xy();
+        let distance = position.distance(target);
+        let angle = get_rotate_radian(position, target).wrap_radian();
+        (distance <= range && (angle - transform.rotation.wrap_radian()).normalize().abs() <= SEEKER_HALF_ANGLE)
+            .then_some((distance, angle))
+    }
+}
+
+/// a torpedo lured onto a sonar decoy, chased instead of any target until the decoy stops emitting
+///
+/// rolled by the server, replicated to other clients and sent to the owner so every copy agrees
+#[derive(Component, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Spoofed(pub Entity);
+
+impl MapEntities for Spoofed {
+    fn map_entities<E: EntityMapper>(&mut self, entity_mapper: &mut E) {
+        self.0 = entity_mapper.get_mapped(self.0);
+    }
+}
+
+impl WeaponType {
+    /// how far the seeker can see in pixels, None if unguided
+    pub fn seeker_range(&self) -> Option<f32> {
+        match self {
+            Self::Torpedo => Some(600.0),
+            Self::Missle => Some(1500.0),
+            Self::AntiAir => Some(800.0),
+            _ => None,
+        }
+    }
+    /// missiles skim the surface, torpedoes can't reach the air and anti-air only engages the air
+    pub fn seeks(&self, domain: Domain) -> bool {
+        match self {
+            Self::Torpedo => matches!(domain, Domain::Surface | Domain::Submerged),
+            Self::Missle => domain == Domain::Surface,
+            Self::AntiAir => domain == Domain::Air,
+            _ => false,
+        }
+    }
+    /// whether the weapon can hit a boat in `domain` on contact, airborne weapons come down on the surface
+    pub fn strikes(&self, domain: Domain) -> bool {
+        match self.domain() {
+            Some(Domain::Air) => domain == Domain::Surface,
+            _ => matches!(domain, Domain::Surface | Domain::Submerged),
+        }
+    }
+    /// where the weapon is while travelling, None if it can't be targeted
+    pub fn domain(&self) -> Option<Domain> {
+        matches!(self, Self::Missle | Self::Rocket | Self::RocketTorpedo).then_some(Domain::Air)
+    }
+}
+
+/// keeps or reacquires each [`Seeker`]'s target and steers towards it
+fn seek(
+    weapons: Query<(&Transform, &Weapon, Option<&LaunchedBy>, Option<&Spoofed>, &mut Seeker, &mut TargetRotation)>,
+    boats: Query<(&CustomTransform, &ZIndex, Entity), (With<Boat>, Without<Sinking>)>,
+    airborne: Query<(&Transform, &Weapon, Option<&LaunchedBy>, Entity)>,
+    decoys: Query<(&Transform, &Travelled)>,
+) {
+    for (transform, weapon, launched_by, spoofed, mut seeker, mut target_rotation) in weapons {
+        if seeker.arming_ticks > 0 {
+            seeker.arming_ticks -= 1;
+            continue;
+        }
+        let weapon_type = weapon.weapon_type();
+        let position = transform.translation.xy();
+        let launcher = launched_by.map(|l| l.0);
+
+        // a lured seeker ignores its cone, falling back to acquisition once the decoy goes quiet
+        if let Some(&Spoofed(decoy)) = spoofed
+            && let Ok((decoy_transform, travelled)) = decoys.get(decoy)
+            && travelled.decoy_emitting()
+        {
+            seeker.target = Some(decoy);
+            target_rotation.0 = get_rotate_radian(position, decoy_transform.translation.xy()).wrap_radian();
+            continue;
+        }
+
+        // (id, position, domain) of everything not launched by the same boat
+        let boat_candidate = |(custom, depth, id): (&CustomTransform, &ZIndex, Entity)| {
+            (Some(id) != launcher).then(|| (id, custom.position.0, Domain::of_depth(*depth)))
+        };
+        let air_candidate = |(other, other_weapon, other_launcher, id): (&Transform, &Weapon, Option<&LaunchedBy>, Entity)| {
+            let friendly = launcher.is_some() && other_launcher.map(|l| l.0) == launcher;
+            let domain = other_weapon.weapon_type().domain()?;
+            (!friendly).then(|| (id, other.translation.xy(), domain))
+        };
+        // the distance and angle towards the candidate if it's a valid target
+        let visible = |(id, target, domain): (Entity, Vec2, Domain)| {
+            if !weapon_type.seeks(domain) {
+                return None;
+            }
+            Seeker::sight(weapon_type, transform, target).map(|(distance, angle)| (id, distance, angle))
+        };
+
+        // a locked target stays locked until it leaves the cone
+        let locked = seeker.target
+            .and_then(|id| boats.get(id).ok().and_then(boat_candidate).or_else(|| airborne.get(id).ok().and_then(air_candidate)))
+            .and_then(visible);
+        let acquired = locked.or_else(|| {
+            boats.iter().filter_map(boat_candidate)
+                .chain(airborne.iter().filter_map(air_candidate))
+                .filter_map(visible)
+                .min_by(|(_, a, _), (_, b, _)| a.total_cmp(b))
+        });
+
+        seeker.target = acquired.map(|(id, ..)| id);
+        if let Some((.., angle)) = acquired {
+            target_rotation.0 = angle;
+        }
+    }
+}
+
 fn rotate_weapon(q: Query<(&mut Transform, &TargetRotation, &Weapon)>) {  // simply don't add TargetRotation for weapons e.g. shells
     for (mut transform, target, weapon) in q {
         let max_turn_radian = weapon.max_turn_radian();
@@ -224,8 +666,8 @@ fn rotate_weapon(q: Query<(&mut Transform, &TargetRotation, &Weapon)>) {  // sim
     }
 }
 
-fn move_weapon(query: Query<(&mut Transform, &Weapon, &mut LastSpeed)>) {
-    for (mut transform, weapon, mut last_speed) in query {
+fn move_weapon(query: Query<(&mut Transform, &Weapon, &mut LastSpeed, &mut Travelled)>, time: Res<Time<Fixed>>) {
+    for (mut transform, weapon, mut last_speed, mut travelled) in query {
         let mut speed = last_speed.0;
         let speed_diff = weapon.max_speed() - last_speed.0;
         let acceleration = weapon.acceleration();
@@ -243,6 +685,145 @@ fn move_weapon(query: Query<(&mut Transform, &Weapon, &mut LastSpeed)>) {
         // update transform
         let move_by = move_with_rotation(transform.rotation.wrap_radian(), speed);
         transform.translation += move_by;
+
+        travelled.distance += move_by.length();
+        travelled.time += time.delta();
+    }
+}
+
+/// the server detonates them, see [`DEPTH_CHARGE_FUSE_RADIUS`](crate::DEPTH_CHARGE_FUSE_RADIUS)
+fn sink_depth_charges(query: Query<(&mut Transform, &Weapon)>) {
+    for (mut transform, weapon) in query {
+        if weapon.weapon_type() != WeaponType::DepthCharge {
+            continue;
+        }
+        transform.translation.z = (transform.translation.z - crate::DEPTH_CHARGE_SINK_SPEED).max(*crate::OCEAN_FLOOR);
+    }
+}
+
+/// the rocket stage of a weapon with a [`Weapon::payload`], inserted by whoever simulates the weapon authoritatively
+///
+/// flies unguided until [`Travelled::distance`] reaches the aim point, then splashes down and is replaced by its payload.
+/// others' copies are left to replication
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Staging {
+    /// pixels
+    release_at: f32,
+}
+
+impl Staging {
+    /// None if the weapon has no payload, the release is clamped to [`Weapon::range`]
+    pub fn new(weapon: Weapon, origin: Vec2, aim: Vec2) -> Option<Self> {
+        weapon.payload()?;
+        let range = weapon.range().unwrap_or(f32::INFINITY);
+        Some(Self {
+            release_at: origin.distance(aim).min(range),
+        })
+    }
+}
+
+/// triggered when a [`Staging`] weapon released its payload, `entity` being the payload
+///
+/// the stage is despawned right after the observers run
+#[derive(Debug, EntityEvent)]
+pub struct Staged {
+    pub entity: Entity,
+    pub stage: Entity,
+}
+
+/// spawns the payload of every [`Staging`] weapon that reached its aim point at the ocean surface
+fn release_payloads(
+    mut commands: Commands,
+    stages: Query<(&Transform, &Weapon, &Travelled, &Staging, Option<&LaunchedBy>, Entity)>,
+) {
+    for (transform, weapon, travelled, staging, launched_by, stage_id) in stages {
+        if travelled.distance < staging.release_at {
+            continue;
+        }
+        let Some(payload) = weapon.payload() else {
+            continue;
+        };
+        let rotation = transform.rotation.wrap_radian();
+
+        let mut released = commands.spawn((
+            Transform {
+                translation: transform.translation.xy().extend(*crate::OCEAN_SURFACE),
+                rotation: transform.rotation,
+                ..default()
+            },
+            payload,
+            TargetRotation(rotation),
+            LastSpeed(Speed::ZERO),
+        ));
+        if let Some(&launched_by) = launched_by {
+            released.insert(launched_by);
+        }
+        if let Some(seeker) = Seeker::new(payload) {
+            released.insert(seeker);
+        }
+        let payload_id = released.id();
+
+        commands.trigger(Staged { entity: payload_id, stage: stage_id });
+        commands.entity(stage_id).despawn();
+    }
+}
+
+/// a shell arcing towards where it lands, replicated so every client lands it on the same spot
+///
+/// shells don't hit anything in the air, see [`Landed`]
+#[derive(Component, Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Ballistic {
+    /// pixels
+    flight: f32,
+}
+
+impl Ballistic {
+    /// None if not a shell, otherwise also returns the dispersed heading
+    ///
+    /// dispersion is seeded by the server, which sends it to the firing client in [`WeaponRollBack::Transform`](crate::protocol::WeaponRollBack::Transform).
+    /// the client predicts its own shot without any, `seed` None
+    pub fn new(weapon: Weapon, origin: Vec2, aim: Vec2, heading: Radian, seed: Option<u64>) -> Option<(Self, Radian)> {
+        if weapon.weapon_type() != WeaponType::Shell {
+            return None;
+        }
+        let (spread, range_spread) = seed.map_or((0.0, 0.0), |seed| (seeded_unit(seed, 0), seeded_unit(seed, 1)));
+        let spread = SHELL_SPREAD * spread;
+        let flight = origin.distance(aim) * (1.0 + SHELL_RANGE_SPREAD * range_spread);
+        let range = weapon.range().unwrap_or(f32::INFINITY);
+
+        Some((Self { flight: flight.min(range) }, heading.rotate_local_z_ret(spread)))
+    }
+    /// 0.0 at launch and landing, 1.0 at the top of the arc
+    pub fn height(&self, travelled: &Travelled) -> f32 {
+        let t = (travelled.distance / self.flight).clamp(0.0, 1.0);
+        4.0 * t * (1.0 - t)
+    }
+}
+
+/// triggered when a [`Ballistic`] shell hits the water, right before it's despawned
+///
+/// the server applies splash damage, see [`SHELL_SPLASH_RADIUS`](crate::SHELL_SPLASH_RADIUS)
+#[derive(Debug, EntityEvent)]
+pub struct Landed {
+    pub entity: Entity,
+    pub position: Vec2,
+}
+
+fn land_shells(mut commands: Commands, shells: Query<(&Transform, &Travelled, &Ballistic, Entity)>) {
+    for (transform, travelled, ballistic, id) in shells {
+        if travelled.distance < ballistic.flight {
+            continue;
+        }
+        commands.trigger(Landed { entity: id, position: transform.translation.xy() });
+        commands.entity(id).despawn();
+    }
+}
+
+fn despawn_expired_weapon(mut commands: Commands, weapons: Query<(&Weapon, &Travelled, Entity)>) {
+    for (weapon, travelled, id) in weapons {
+        if travelled.expired(*weapon) {
+            commands.entity(id).despawn();
+        }
     }
 }
 
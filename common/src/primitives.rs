@@ -100,6 +100,46 @@ impl fmt::Display for WeaponCounter {
         Ok(())
     }
 }
+
+/// hit points of a [`Boat`], only ever modified by the server
+#[derive(Component, Serialize, Deserialize, PartialEq, Copy, Clone, Debug, Reflect)]
+pub struct Health {
+    current: f32,
+    max: f32,
+}
+
+impl Health {
+    /// full health
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max
+        }
+    }
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+    /// within range `0.0..=1.0`
+    pub fn ratio(&self) -> f32 {
+        self.current / self.max
+    }
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+    /// returns true if the damage killed the boat
+    pub fn damage(&mut self, amount: f32) -> bool {
+        self.current = (self.current - amount).max(0.0);
+        self.is_dead()
+    }
+    /// changes the maximum health while keeping the [`ratio`](Self::ratio), e.g. on upgrade
+    pub fn set_max(&mut self, max: f32) {
+        self.current = self.ratio() * max;
+        self.max = max;
+    }
+}
 // maybe Trait on Rect？
 /// useful helpers like getting corners and large bounding box
 #[derive(Debug, Clone, Copy)]
@@ -413,7 +453,7 @@ impl WrapRadian for Radian {
 }
 
 /// only used by the [`Boat`] entity to indicate the depth without bloating [`CustomTransform`] as a Component currently,  
-/// also tricky because [`CustomTransform`] is both locally predicted and remotely while [`ZIndex`] should be client-authoritive
+/// predicted like [`CustomTransform`] from [`DiveIntent`](crate::protocol::DiveIntent), see [`DivePlugin`](crate::DivePlugin)
 /// 
 /// ### Important
 /// only for the physics depth, NOT the rendering depth ([`Transform::translation`])
@@ -876,12 +916,6 @@ pub struct UpgradeEvent {
     pub target: Boat
 }
 
-#[cfg(feature = "client")]
-#[derive(Debug, Event)]
-pub struct MaybePushToSurface {
-    pub last_boat: Boat
-}
-
 pub trait RoughEq<Rhs = Self> {
     /// returns true if two vals are roughly equal (counting floats to be equal if difference below 0.001
     fn rough_eq(&self, rhs: &Rhs) -> bool;
@@ -943,4 +977,26 @@ mod tests {
 
         assert!(knots_speed.rough_eq(&meter_speed));
     }
+    #[test]
+    fn test_health() {
+        let mut health = Health::new(100.0);
+        assert!(!health.damage(40.0));
+        health.set_max(200.0);
+        assert_eq!(health.current(), 120.0);
+        assert!(health.damage(500.0));
+        assert_eq!(health.current(), 0.0);
+    }
+    #[test]
+    fn test_level_boat_kinds() {
+        use crate::SubKind;
+
+        for level in Level::iter() {
+            let kinds = level.avaliable_boats().map(|boat| boat.sub_kind()).collect::<Vec<_>>();
+            assert!(kinds.contains(&SubKind::SurfaceShip), "{level:?} has no surface ship");
+            // the spritesheet has no submarine smaller than the level 2 Type VIIC
+            if level != Level::One {
+                assert!(kinds.contains(&SubKind::Submarine), "{level:?} has no submarine");
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -2,7 +2,7 @@
 
 use std::f32::consts::{FRAC_PI_2, PI};
 use crate::{
-    OCEAN_SURFACE, OIL_RIG_Z, POINTS_Z, boat::Boat, primitives::{CustomTransform, DisplayScore, LastSpeed, PlayerStats, Point, Radian, Size, Speed, TargetRotation}
+    OCEAN_SURFACE, OIL_RIG_Z, POINTS_Z, boat::{Boat, Sinking}, primitives::{CustomTransform, DisplayScore, Health, LastSpeed, PlayerStats, Point, Radian, Size, Speed, TargetRotation}
 };
 use bevy::{ecs::entity::MapEntities, prelude::*};
 use lightyear::{
@@ -11,7 +11,10 @@ use lightyear::{
 };
 use macros::{FetchSprite};
 use serde::{Deserialize, Serialize};
+use crate::definitions::Definitions;
 use crate::primitives::{Position, ZIndex};
+use crate::movement::{Ballistic, LaunchedBy, Spoofed};
+use crate::turret::Turrets;
 use crate::weapon::Weapon;
 use crate::world::WorldSize;
 
@@ -21,6 +24,8 @@ pub struct SendToServer;
 // ordered reliable
 pub struct SendToClientOrdered;
 pub struct SendToServerOrdered;
+/// sequenced unreliable with a low priority, a lost [`Leaderboard`] is replaced by the next one anyway
+pub struct SendLeaderboard;
 
 /// ship's head's radian with positive x-axis
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Reflect, PartialEq)]
@@ -28,8 +33,23 @@ pub struct Rotate(pub Option<Radian>);
 /// speed is negative on reverse
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Reflect, PartialEq)]
 pub struct Move(pub Option<Speed>);
+/// which way a submarine is heading in depth, the [`ZIndex`] itself is simulated by [`DivePlugin`](crate::DivePlugin)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Reflect, PartialEq, Eq)]
+pub enum DiveIntent {
+    /// stays at the current depth
+    #[default]
+    Hold,
+    /// towards [`Boat::max_depth`]
+    Dive,
+    /// towards [`OCEAN_SURFACE`]
+    Surface,
+}
+/// whether active sonar is on, ignored for boats without [`Boat::has_active_sonar`]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Reflect, PartialEq)]
+pub struct PingSonar(pub bool);
+/// world position the [`Turrets`] turn towards
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, Reflect, PartialEq)]
-pub struct ZIndexUpdate(pub Option<ZIndex>);
+pub struct AimTurrets(pub Option<Vec2>);
 
 impl MapEntities for Rotate {
     fn map_entities<E: EntityMapper>(&mut self, _entity_mapper: &mut E) {}
@@ -37,7 +57,13 @@ impl MapEntities for Rotate {
 impl MapEntities for Move {
     fn map_entities<E: EntityMapper>(&mut self, _entity_mapper: &mut E) {}
 }
-impl MapEntities for ZIndexUpdate {
+impl MapEntities for DiveIntent {
+    fn map_entities<E: EntityMapper>(&mut self, _entity_mapper: &mut E) {}
+}
+impl MapEntities for PingSonar {
+    fn map_entities<E: EntityMapper>(&mut self, _entity_mapper: &mut E) {}
+}
+impl MapEntities for AimTurrets {
     fn map_entities<E: EntityMapper>(&mut self, _entity_mapper: &mut E) {}
 }
 
@@ -135,17 +161,39 @@ impl Ease for PointTransform {
 }
 
 /// currently implemented as a message, to-server
+///
+/// the server finds the [`Boat`] from the client sending it and recomputes where the weapon leaves from,
+/// `position` and the rotations are only compared against its own to send a [`WeaponRollBack::Transform`]
 #[derive(Debug, Deserialize, Serialize)]
 pub struct SpawnWeapon {
     pub weapon: Weapon,
     pub position: Vec3,
-    // currently no turrets etc
+    /// index into [`Boat::mounts`](crate::Boat::mounts) the weapon leaves from, None for the boat's centre
+    pub mount: Option<u8>,
+    /// the mount's azimuth, or the hull's rotation without one
     pub starting_rotation: Radian,
     pub end_rotation: Radian,
+    /// cursor position when fired, where [`Staging`](crate::Staging) weapons release their payload and [`Ballistic`] shells land
+    pub target: Vec2,
     /// to identify the weapon on client-side if server doesn't approve
     pub entity_on_client: EntityOnClient,
-    /// required to replicate weapon to other clients and identifying the [`Boat`] entity on the server
-    pub client_id: PeerId
+}
+
+/// one row of the [`Leaderboard`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub boat: Boat,
+    pub score: u32,
+}
+
+/// the highest scores, sent to every client periodically on [`SendLeaderboard`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Leaderboard {
+    /// from the highest score
+    pub top: Vec<LeaderboardEntry>,
+    /// the receiving client's 1-based rank and row, even outside `top`. None without a boat
+    pub own: Option<(u32, LeaderboardEntry)>,
 }
 
 /// replicated by server on spawning the main boat entity
@@ -153,6 +201,22 @@ pub struct SpawnWeapon {
 /// mainly used by client to specify the [`Boat`] entity targeting on server in a message
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Component, Copy, Reflect)]
 pub struct EntityOnServer(pub u64);
+/// the player's name, replicated on the main boat entity
+///
+/// chosen when joining, see `/join` on the server
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Component, Reflect)]
+pub struct Nickname(pub String);
+
+impl Nickname {
+    /// in chars, a uniqueness suffix may add a few more
+    pub const MAX_LEN: usize = 16;
+
+    /// whether a player may type `c` in their nickname
+    pub fn is_allowed_char(c: char) -> bool {
+        c.is_ascii_alphanumeric() || matches!(c, ' ' | '_' | '-')
+    }
+}
+
 /// specifying Weapon to rollback on client for now
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Component, Copy, Reflect)]
 pub struct EntityOnClient(pub u64);
@@ -163,6 +227,10 @@ pub enum WeaponRollBack {
     Transform {
         position: Vec3,
         rotation: Radian,
+        /// [`TargetRotation`](crate::primitives::TargetRotation), after a [`Ballistic`] shell's dispersion
+        end_rotation: Radian,
+        /// the server's dispersed flight for shells, always sent since the client predicts without dispersion
+        ballistic: Option<Ballistic>,
         entity: EntityOnClient
     },
     /// client should +1 on the weaponcounter
@@ -171,11 +239,64 @@ pub enum WeaponRollBack {
     }
 }
 
+/// sent to the client that fired the weapon, since it simulates its own copy locally
+///
+/// a released payload is identified by its rocket stage's entity
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WeaponImpact {
+    pub entity: EntityOnClient
+}
+
+/// sent to the client that fired a torpedo when the server lures it onto a sonar decoy
+///
+/// other clients receive the replicated [`Spoofed`] instead
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WeaponSpoofed {
+    pub entity: EntityOnClient,
+    pub decoy: Entity,
+}
+
+impl MapEntities for WeaponSpoofed {
+    fn map_entities<E: EntityMapper>(&mut self, entity_mapper: &mut E) {
+        self.decoy = entity_mapper.get_mapped(self.decoy);
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ReloadWeapon {
     pub weapon: Weapon,
 }
 
+/// why a boat sank, shown on the death screen
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum DeathReason {
+    Weapon {
+        weapon: Weapon,
+        /// the boat that fired the weapon
+        killer: Boat
+    },
+    Rammed {
+        killer: Boat
+    },
+    OilRig,
+}
+
+/// server-wide message from an admin, shown to every player for a while
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Announcement(pub String);
+
+/// sent to the owner of a boat that started [`Sinking`]
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BoatDestroyed {
+    pub reason: DeathReason,
+    /// score at the time of death
+    pub score: u32
+}
+
+/// client asks for a new level 1 boat after its previous one sank
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RespawnRequest;
+
 #[derive(Debug, Deserialize, Serialize)]
 pub(crate) struct UpgradeMessage {
     pub target: Boat,
@@ -207,27 +328,49 @@ impl Plugin for ProtocolPlugin {
             .replicate()
             .predict()
             .add_linear_interpolation();
-        app.component::<ZIndex>().replicate();
+        app.component::<ZIndex>()
+            .replicate()
+            .predict();
 
         app.component::<EntityOnServer>().replicate();
+        app.component::<Nickname>().replicate();
 
         app.component::<OilRigTransform>().replicate();
         app.component::<PointTransform>().replicate()
             .add_linear_interpolation();
 
         app.component::<PlayerStats>().replicate();
+        app.component::<Health>().replicate();
+        app.component::<Sinking>().replicate();
+        app.register_message::<BoatDestroyed>().add_direction(NetworkDirection::ServerToClient);
+        app.register_message::<RespawnRequest>().add_direction(NetworkDirection::ClientToServer);
         app.register_message::<DisplayScore>().add_direction(NetworkDirection::ServerToClient);
+        app.register_message::<Leaderboard>().add_direction(NetworkDirection::ServerToClient);
+        app.register_message::<Announcement>().add_direction(NetworkDirection::ServerToClient);
+        // sent on connect if the server loaded any
+        app.register_message::<Definitions>().add_direction(NetworkDirection::ServerToClient);
 
         // // MUST register these two for every input
         app.add_plugins(InputPlugin::<Rotate>::default());
         app.add_plugins(InputPlugin::<Move>::default());
-        app.add_plugins(InputPlugin::<ZIndexUpdate>::default());
+        app.add_plugins(InputPlugin::<DiveIntent>::default());
+        app.add_plugins(InputPlugin::<PingSonar>::default());
+        app.add_plugins(InputPlugin::<AimTurrets>::default());
         app.component::<ActionState<Rotate>>().replicate();
         app.component::<ActionState<Move>>().replicate();
-        app.component::<ActionState<ZIndexUpdate>>().replicate();
+        app.component::<ActionState<DiveIntent>>().replicate();
+        app.component::<ActionState<PingSonar>>().replicate();
+        app.component::<ActionState<AimTurrets>>().replicate();
+        app.component::<Turrets>()
+            .replicate()
+            .predict();
 
         app.register_message::<SpawnWeapon>().add_direction(NetworkDirection::ClientToServer);
         app.register_message::<WeaponRollBack>().add_direction(NetworkDirection::ServerToClient);
+        app.register_message::<WeaponImpact>().add_direction(NetworkDirection::ServerToClient);
+        app.register_message::<WeaponSpoofed>()
+            .add_map_entities()
+            .add_direction(NetworkDirection::ServerToClient);
 
         app.register_message::<UpgradeMessage>().add_direction(NetworkDirection::ClientToServer);
         app.register_message::<UpgradeRollback>().add_direction(NetworkDirection::ServerToClient);
@@ -237,6 +380,9 @@ impl Plugin for ProtocolPlugin {
         app.component::<Transform>().replicate_filtered::<With<Weapon>>();
         app.component::<LastSpeed>().replicate();
         app.component::<TargetRotation>().replicate();
+        app.component::<Spoofed>().replicate().add_map_entities();
+        app.component::<LaunchedBy>().replicate().add_map_entities();
+        app.component::<Ballistic>().replicate();
 
         app.register_message::<ReloadWeapon>().add_direction(NetworkDirection::ServerToClient);
 
@@ -262,6 +408,12 @@ impl Plugin for ProtocolPlugin {
             ..default()
         })
             .add_direction(NetworkDirection::ClientToServer);
+        app.add_channel::<SendLeaderboard>(ChannelSettings {
+            mode: ChannelMode::SequencedUnreliable,
+            priority: 0.5,
+            ..default()
+        })
+            .add_direction(NetworkDirection::ServerToClient);
     }
 }
 
@@ -0,0 +1,64 @@
+//! how far a boat can see, the server only replicates to a client what its boat detects
+//!
+//! surface ships rely on their radar, submerged submarines only on sonar, which can't pick up anything in the air
+//!
+//! submarines and ASW ships can also ping with active sonar, see [`PingSonar`](crate::protocol::PingSonar)
+
+use crate::boat::{Boat, SubKind};
+use crate::movement::Domain;
+use crate::primitives::ZIndex;
+use crate::WeaponType;
+
+/// pixels, sonar range while pinging, see [`Sensors::ping`]
+pub const ACTIVE_SONAR_RANGE: f32 = 1500.0;
+/// pixels, anyone this close hears a pinging boat whatever their sensors
+pub const ACTIVE_SONAR_REVEAL_RANGE: f32 = ACTIVE_SONAR_RANGE * 2.0;
+/// pixels, no [`Sensors`] reach further, pinging boats being heard aside
+pub const MAX_SENSOR_RANGE: f32 = ACTIVE_SONAR_RANGE;
+
+/// detection ranges in pixels, see [`Boat::sensors`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sensors {
+    pub visual: f32,
+    pub radar: f32,
+    pub sonar: f32,
+}
+
+impl Sensors {
+    const SURFACE_SHIP: Self = Self { visual: 900.0, radar: 1400.0, sonar: 500.0 };
+    const HOVER_CRAFT: Self = Self { visual: 900.0, radar: 1000.0, sonar: 0.0 };
+    /// masts are lower than a ship's
+    const SURFACED_SUBMARINE: Self = Self { visual: 700.0, radar: 900.0, sonar: 800.0 };
+    const SUBMERGED_SUBMARINE: Self = Self { visual: 0.0, radar: 0.0, sonar: 1100.0 };
+
+    /// while pinging, every submerged contact within [`ACTIVE_SONAR_RANGE`] is detected
+    pub fn ping(self) -> Self {
+        Self { sonar: self.sonar.max(ACTIVE_SONAR_RANGE), ..self }
+    }
+    /// whether something in `domain` `distance` pixels away is detected
+    pub fn detects(&self, distance: f32, domain: Domain) -> bool {
+        let range = match domain {
+            Domain::Air => self.visual.max(self.radar),
+            Domain::Surface => self.visual.max(self.radar).max(self.sonar),
+            Domain::Submerged => self.sonar,
+        };
+        distance <= range
+    }
+}
+
+impl Boat {
+    /// a submarine loses its visual and radar ranges once submerged
+    pub fn sensors(&self, depth: ZIndex) -> Sensors {
+        match (self.sub_kind(), Domain::of_depth(depth)) {
+            (SubKind::Submarine, Domain::Submerged) => Sensors::SUBMERGED_SUBMARINE,
+            (SubKind::Submarine, _) => Sensors::SURFACED_SUBMARINE,
+            (SubKind::SurfaceShip, _) => Sensors::SURFACE_SHIP,
+            (SubKind::HoverCraft, _) => Sensors::HOVER_CRAFT,
+        }
+    }
+    /// submarines, and ships carrying anti-submarine weapons
+    pub fn has_active_sonar(&self) -> bool {
+        self.sub_kind() == SubKind::Submarine
+            || self.armanents().keys().any(|weapon| matches!(weapon.weapon_type(), WeaponType::DepthCharge | WeaponType::RocketTorpedo))
+    }
+}
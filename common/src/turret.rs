@@ -0,0 +1,162 @@
+//! weapon mounts on the hull, both turning turrets and fixed torpedo tubes
+//!
+//! [`Turrets`] holds the azimuth of every [`Mount`] of the boat, turned towards [`AimTurrets`] by both sides
+//! like [`CustomTransform`] is by [`Rotate`](crate::protocol::Rotate).
+//! the mounts of each boat are declared with `#[mounts(..)]` next to its other stats on [`Boat`],
+//! boats without mounts fire from their centre along the hull
+
+use bevy::prelude::*;
+use lightyear::prelude::input::native::ActionState;
+use serde::{Deserialize, Serialize};
+
+use crate::boat::{Boat, Sinking};
+use crate::primitives::{CustomTransform, NormalizeRadian, Radian, Size};
+use crate::protocol::AimTurrets;
+use crate::WeaponType;
+
+/// turns [`Turrets`] towards [`AimTurrets`], added by both the client and the server
+pub struct TurretPlugin;
+
+impl Plugin for TurretPlugin {
+    fn build(&self, app: &mut App) {
+        // `FixedUpdate` because inputs are tick-synced
+        app.add_systems(FixedUpdate, aim_turrets);
+    }
+}
+
+/// where a weapon of [`Mount::weapon_type`] is launched from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mount {
+    pub weapon_type: WeaponType,
+    /// meters from the centre of the hull, +x towards the bow and +y to port
+    pub position: Vec2,
+    /// azimuth relative to the bow when centred, fixed mounts always point here
+    pub rest: Radian,
+    /// how far either side of `rest` the mount can turn, zero for fixed mounts
+    pub arc: Radian,
+    /// per tick
+    pub turn_rate: Radian,
+}
+
+impl Mount {
+    /// angles in degrees, used by `#[mounts(turret(..))]` on [`Boat`]
+    pub const fn turret(weapon_type: WeaponType, x: f32, y: f32, rest: f32, arc: f32, turn_rate: f32) -> Self {
+        Self {
+            weapon_type,
+            position: Vec2::new(x, y),
+            rest: Radian::from_deg(rest),
+            arc: Radian::from_deg(arc),
+            turn_rate: Radian::from_deg(turn_rate),
+        }
+    }
+    /// used by `#[mounts(fixed(..))]` on [`Boat`]
+    pub const fn fixed(weapon_type: WeaponType, x: f32, y: f32, rest: f32) -> Self {
+        Self::turret(weapon_type, x, y, rest, 0.0, 0.0)
+    }
+    pub fn is_fixed(&self) -> bool {
+        self.turn_rate.0 <= 0.0
+    }
+    /// in pixels
+    pub fn world_position(&self, hull: &CustomTransform) -> Vec2 {
+        hull.position.0 + hull.rotation.to_vec().rotate(self.position * Boat::SIZE_TO_RENDER_MULTIPLIER)
+    }
+    /// whether the mount can point at `azimuth` relative to the bow
+    pub fn allows(&self, azimuth: Radian) -> bool {
+        (azimuth - self.rest).normalize().abs() <= self.arc
+    }
+    /// the azimuth relative to the bow inside the arc closest to `azimuth`
+    pub fn clamp(&self, azimuth: Radian) -> Radian {
+        let offset = (azimuth - self.rest).normalize().0.clamp(-self.arc.0, self.arc.0);
+        (self.rest + Radian(offset)).normalize()
+    }
+    /// the azimuth relative to the bow that would point the mount at `target`, clamped to the arc
+    pub fn bearing(&self, hull: &CustomTransform, target: Vec2) -> Radian {
+        let world = (target - self.world_position(hull)).to_angle();
+        self.clamp(Radian(world - hull.rotation.0).normalize())
+    }
+    /// turns within the arc, never through the blind side behind `rest`
+    fn turn_towards(&self, current: Radian, desired: Radian) -> Radian {
+        let current_offset = (current - self.rest).normalize().0;
+        let desired_offset = (self.clamp(desired) - self.rest).normalize().0;
+        let step = (desired_offset - current_offset).clamp(-self.turn_rate.0, self.turn_rate.0);
+        (self.rest + Radian(current_offset + step)).normalize()
+    }
+}
+
+impl Boat {
+    /// the mount of `weapon_type` pointing closest to `target`, None if the boat fires it from its centre
+    pub fn best_mount(&self, weapon_type: WeaponType, hull: &CustomTransform, turrets: &Turrets, target: Vec2) -> Option<usize> {
+        let off_target = |(i, mount): (usize, &Mount)| {
+            let azimuth = turrets.0.get(i).copied().unwrap_or(mount.rest);
+            let wanted = Radian((target - mount.world_position(hull)).to_angle() - hull.rotation.0).normalize();
+            (i, (wanted - azimuth).normalize().abs().0)
+        };
+        self.mounts().iter().enumerate()
+            .filter(|(_, mount)| mount.weapon_type == weapon_type)
+            .map(off_target)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+    }
+}
+
+/// azimuth of each of [`Boat::mounts`] relative to the bow, replicated and predicted like [`CustomTransform`]
+#[derive(Component, Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Turrets(pub Vec<Radian>);
+
+impl Turrets {
+    /// every mount at rest
+    pub fn new(boat: Boat) -> Self {
+        Self(boat.mounts().iter().map(|mount| mount.rest).collect())
+    }
+    /// the direction weapons leave the mount in world space
+    pub fn world_azimuth(&self, index: usize, hull: &CustomTransform) -> Radian {
+        (hull.rotation + self.0[index]).normalize()
+    }
+}
+
+fn aim_turrets(boats: Query<(&ActionState<AimTurrets>, &CustomTransform, &Boat, &mut Turrets), Without<Sinking>>) {
+    for (aim, custom, boat, mut turrets) in boats {
+        let mounts = boat.mounts();
+        // upgraded to a boat with different mounts
+        if turrets.0.len() != mounts.len() {
+            *turrets = Turrets::new(*boat);
+        }
+        let AimTurrets(Some(target)) = aim.0 else {
+            continue;
+        };
+
+        let mut changed = false;
+        for (mount, azimuth) in mounts.iter().zip(turrets.bypass_change_detection().0.iter_mut()) {
+            if mount.is_fixed() {
+                continue;
+            }
+            let turned = mount.turn_towards(*azimuth, mount.bearing(custom, target));
+            changed |= turned != *azimuth;
+            *azimuth = turned;
+        }
+        if changed {
+            turrets.set_changed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::*;
+
+    #[test]
+    fn test_boat_mounts() {
+        for boat in Boat::iter() {
+            for weapon_type in [WeaponType::Shell, WeaponType::Torpedo] {
+                if boat.armanents().keys().any(|weapon| weapon.weapon_type() == weapon_type) {
+                    assert!(
+                        boat.mounts().iter().any(|mount| mount.weapon_type == weapon_type),
+                        "{boat:?} carries {weapon_type:?} without a mount for it"
+                    );
+                }
+            }
+        }
+    }
+}
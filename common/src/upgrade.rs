@@ -57,10 +57,10 @@ pub use client::UpgradeEventCommonFinished;
 pub use server::UpgradeEventCommonFinished;
 #[cfg(feature = "client")]
 mod client {
-    use crate::boat::{CircleHud, SubKind};
+    use crate::boat::CircleHud;
     use crate::{BoatReverseNegative, BoatReversePositive, BoatType, CIRCLE_HUD, circle_hud_mesh};
     use crate::protocol::{EntityOnServer, SendToServerOrdered};
-    use crate::primitives::{MaybePushToSurface, PlayerStats, UpgradeEvent, UpgradeRollbackEvent};
+    use crate::primitives::{PlayerStats, UpgradeEvent, UpgradeRollbackEvent};
     use super::*;
 
     /// the updatng of components have finished (in `common`)
@@ -97,9 +97,6 @@ mod client {
         let (mut boat, mut weapon_counter, mut player_stats) = query.into_inner();
 
         *player_stats.level_mut() = target.level();
-        if boat.sub_kind() == SubKind::Submarine && target.sub_kind() != SubKind::Submarine {  // maybe add depth to sub diving
-            commands.trigger(MaybePushToSurface { last_boat: *boat });
-        }
         upgrade_components(
             target,
             &mut boat,
@@ -150,7 +147,7 @@ mod client {
 
 #[cfg(feature = "server")]
 mod server {
-    use crate::{primitives::PlayerStats, protocol::SendToClient, BoatClientId};
+    use crate::{primitives::{Health, PlayerStats}, protocol::SendToClient, BoatClientId};
     use super::*;
 
     /// making sure that the WeaponCounter is correct when listens for messsages from client firing weapon
@@ -169,7 +166,7 @@ mod server {
         mut sender: ServerMultiMessageSender,
         server: Single<&Server>,
     
-        mut stats: Query<(&mut PlayerStats, &BoatClientId, &mut Boat, &mut WeaponCounter)>,
+        mut stats: Query<(&mut PlayerStats, &BoatClientId, &mut Boat, &mut WeaponCounter, &mut Health)>,
 
         mut commands: Commands
     ) {
@@ -178,7 +175,8 @@ mod server {
                 if let Ok((
                     mut stat,
                     client_id,
-                    mut boat, mut weapon_counter
+                    mut boat, mut weapon_counter,
+                    mut health
                 )) = stats.get_mut(Entity::from_bits(entity_on_server.0)) {
                     if stat.can_upgrade(target) {
                         debug!("Client {client_id:?} upgrading to {target:?}");
@@ -188,6 +186,7 @@ mod server {
                             &mut boat,
                             &mut weapon_counter
                         );
+                        health.set_max(target.max_health());
 
                         commands.trigger(UpgradeEventCommonFinished {
                             entity: Entity::from_bits(entity_on_server.0)
@@ -145,6 +145,18 @@ pub fn avaliable_cords(position: Vec2, radius: f32) -> (RangeInclusive<f32>, Ran
     (x, y)
 }
 
+/// a deterministic value within -1.0..=1.0 for `seed` and `salt`, same on every machine
+///
+/// splitmix64, for spreading values without sharing an rng between server and client
+pub fn seeded_unit(seed: u64, salt: u64) -> f32 {
+    let mut z = seed.wrapping_add(salt.wrapping_mul(0x9E37_79B9_7F4A_7C15)).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    // top 24 bits fit in an f32 exactly
+    (z >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
 /// returns the (radius, darkness (0..1)) to be passed into shaders
 ///
 /// the closer to the surface(0.0), the bigger the radius, smaller the darkness and vice versa
@@ -493,6 +505,12 @@ impl<K: PartialEq, V> OrderedHashMap<K, V> {
     }
 }
 
+impl<K, V> FromIterator<(K, V)> for OrderedHashMap<K, V> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        OrderedHashMap { vec: iter.into_iter().collect() }
+    }
+}
+
 impl<K, V> IntoIterator for OrderedHashMap<K, V> {
     type Item = (K, V);
     type IntoIter = vec::IntoIter<Self::Item>;
@@ -728,6 +746,12 @@ mod tests {
         assert_eq!(result, 50.0);
     }
     #[test]
+    fn test_seeded_unit() {
+        assert_eq!(seeded_unit(42, 0), seeded_unit(42, 0));
+        assert_ne!(seeded_unit(42, 0), seeded_unit(42, 1));
+        assert!((0..1000).all(|seed| (-1.0..=1.0).contains(&seeded_unit(seed, 0))));
+    }
+    #[test]
     fn test_div_overlay() {
         let target = calculate_diving_overlay(-0.4.wrap_z(), -2.0.wrap_z(), 30.0, 50.0, 0.4);
 
@@ -1,74 +1,247 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
-use macros::{FetchSprite, MaxSpeed, Reload, Size, WeaponType};
+use macros::{Expiry, FetchSprite, MaxSpeed, Reload, Size, WeaponType};
 use serde::{Deserialize, Serialize};
-use crate::primitives::Radian;
+use crate::primitives::{Radian, Size};
 use crate::{DEFAULT_MAX_TURN_DEG, primitives::Speed};
+use crate::movement::Travelled;
 
-#[derive(FetchSprite, Size, WeaponType, MaxSpeed, Reload, Debug, Component, Clone, Copy, PartialEq, Serialize, Deserialize, Hash, Eq)]
+#[derive(FetchSprite, Size, WeaponType, MaxSpeed, Reload, Expiry, Debug, Component, Clone, Copy, PartialEq, Serialize, Deserialize, Hash, Eq)]
+#[require(Travelled)]
 #[allow(non_camel_case_types)]
 pub enum Weapon {
     #[length = 6.2]
     #[weapon_type = "Torpedo"]
     #[max_speed = 29]
     #[reload = 8]
+    #[range = 350]
     Mark18,
     // Shell_heightxlengthMmr  length and height in milimeters (apparently since mk48 divide it by 1000)
     #[max_speed = 1243.6]
     #[reload = 8.7]
+    #[range = 250]
     Shell_57x441Mmr,
     #[length = 0.4]
     #[weapon_type = "DepthCharge"]
     #[max_speed = "None"]  // TODO what?
     #[reload = 16]
+    #[lifetime = 5]  // detonates on the floor before then
     Mark9,
     #[length = 7.2]
     #[weapon_type = "Torpedo"]
     #[max_speed = 45.1]
     #[reload = 8]
+    #[range = 400]
     Type53,
     #[max_speed = 1312.1]
     #[reload = 8]
+    #[range = 150]
     Shell_25x129Mmr,
     #[max_speed = 1151.7]
     #[reload = 9.7]
+    #[range = 350]
     Shell_127x680Mmr,
     #[length = 1.1]
     #[weapon_type = "Rocket"]
     #[max_speed = 388.8]
     #[reload = 2.5]
+    #[range = 300]
     Of45,
     #[max_speed = 1332.5]
     #[reload = 12.6]
+    #[range = 550]
     Shell_300x1400Mmr,
     #[length = 7.9]
     #[weapon_type = "Torpedo"]
     #[max_speed = 40]
     #[reload = 12]
+    #[range = 450]
     Set65,
     #[length = 8.4]
     #[weapon_type = "Missle"]
     #[max_speed = 1931.9]
     #[reload = 12]
+    #[range = 900]
     BrahMos,
     /// following name on https://mk48.io/ships/yasen/
     #[length = 6.5]
     #[weapon_type = "RocketTorpedo"]
     #[max_speed = 388.8]
     #[reload = 20]
+    #[range = 600]
     #[json = "Rpk6"]
-    Vodopad,  // todo associated 82R
+    Vodopad,
     #[length = 1.6]
     #[weapon_type = "AntiAir"]
     #[max_speed = 1108]
     #[reload = 16]
+    #[range = 250]
     Igla,
     #[length = 1.5]
     #[weapon_type = "SonarDecoy"]
     #[max_speed = 23.2]
     #[reload = 20]
-    Brosok
+    #[lifetime = 10]
+    Brosok,
+    #[length = 5.8]
+    #[weapon_type = "Torpedo"]
+    #[max_speed = 55]
+    #[reload = 10]
+    #[range = 500]
+    Mark48,
+    #[length = 2.72]
+    #[weapon_type = "Torpedo"]
+    #[max_speed = 40]
+    #[reload = 8]
+    #[range = 300]
+    Mark54,
+    #[length = 6.6]
+    #[weapon_type = "Torpedo"]
+    #[max_speed = 50]
+    #[reload = 10]
+    #[range = 500]
+    Dm2A4,
+    #[length = 2.85]
+    #[weapon_type = "Torpedo"]
+    #[max_speed = 40]
+    #[reload = 8]
+    #[range = 300]
+    Tau2000,
+    #[length = 4.6]
+    #[weapon_type = "Missle"]
+    #[max_speed = 464]
+    #[reload = 18]
+    #[range = 700]
+    Harpoon,
+    #[length = 5.56]
+    #[weapon_type = "Missle"]
+    #[max_speed = 477]
+    #[reload = 30]
+    #[range = 1000]
+    Tomahawk,
+    #[length = 5.8]
+    #[weapon_type = "Missle"]
+    #[max_speed = 595]
+    #[reload = 20]
+    #[range = 600]
+    P15,
+    #[length = 10]
+    #[weapon_type = "Missle"]
+    #[max_speed = 1058]
+    #[reload = 30]
+    #[range = 1000]
+    P700,
+    #[length = 8.9]
+    #[weapon_type = "Missle"]
+    #[max_speed = 530]
+    #[reload = 25]
+    #[range = 900]
+    Kalibr,
+    #[length = 8.2]
+    #[weapon_type = "Missle"]
+    #[max_speed = 540]
+    #[reload = 22]
+    #[range = 800]
+    Yj18,
+    #[length = 3.96]
+    #[weapon_type = "Missle"]
+    #[max_speed = 600]
+    #[reload = 18]
+    #[range = 700]
+    Nsm,
+    #[length = 4.33]
+    #[weapon_type = "Missle"]
+    #[max_speed = 560]
+    #[reload = 18]
+    #[range = 700]
+    Rbs15,
+    #[length = 4.5]
+    #[weapon_type = "RocketTorpedo"]
+    #[max_speed = 600]
+    #[reload = 20]
+    #[range = 500]
+    Asroc,
+    /// only released by [`Weapon::Vodopad`]
+    #[length = 3.8]
+    #[weapon_type = "Torpedo"]
+    #[max_speed = 41]
+    #[reload = 20]
+    #[range = 400]
+    #[json = "82R"]
+    Type82R,
+    #[length = 3.66]
+    #[weapon_type = "AntiAir"]
+    #[max_speed = 1700]
+    #[reload = 12]
+    #[range = 400]
+    Essm,
+    #[length = 2.79]
+    #[weapon_type = "AntiAir"]
+    #[max_speed = 1100]
+    #[reload = 10]
+    #[range = 250]
+    Rim116,
+    #[length = 7.25]
+    #[weapon_type = "AntiAir"]
+    #[max_speed = 2300]
+    #[reload = 20]
+    #[range = 600]
+    S300,
+    #[length = 6.8]
+    #[weapon_type = "AntiAir"]
+    #[max_speed = 2300]
+    #[reload = 20]
+    #[range = 600]
+    Hq9,
+    #[length = 4.5]
+    #[weapon_type = "AntiAir"]
+    #[max_speed = 1300]
+    #[reload = 14]
+    #[range = 450]
+    Barak8,
+    /// launched from a submerged submarine
+    #[length = 2.5]
+    #[weapon_type = "AntiAir"]
+    #[max_speed = 700]
+    #[reload = 20]
+    #[range = 300]
+    Idas,
+    #[max_speed = 1798.2]
+    #[reload = 4]
+    #[range = 300]
+    Shell_76x636Mmr,
+    #[max_speed = 1652.4]
+    #[reload = 6]
+    #[range = 400]
+    Shell_130x720Mmr,
+    #[max_speed = 1594]
+    #[reload = 26]
+    #[range = 600]
+    Shell_380x1700Mmr,
+    #[max_speed = 1481.3]
+    #[reload = 30]
+    #[range = 650]
+    Shell_406x1800Mmr,
+    #[max_speed = 1516.3]
+    #[reload = 40]
+    #[range = 700]
+    Shell_458x1980Mmr,
 }
 
+/// [`ZIndex`](crate::primitives::ZIndex) a depth charge sinks every tick until [`OCEAN_FLOOR`](crate::OCEAN_FLOOR)
+pub const DEPTH_CHARGE_SINK_SPEED: f32 = 0.002;
+/// pixels, a submarine this close within [`DEPTH_CHARGE_DEPTH_BAND`] sets off a depth charge
+pub const DEPTH_CHARGE_FUSE_RADIUS: f32 = 40.0;
+/// pixels, damage falls off linearly towards the edge
+pub const DEPTH_CHARGE_BLAST_RADIUS: f32 = 120.0;
+/// maximum [`ZIndex`](crate::primitives::ZIndex) difference for the fuse and the blast
+pub const DEPTH_CHARGE_DEPTH_BAND: f32 = 0.1;
+/// how long after launch a sonar decoy can lure torpedoes, it keeps drifting until its lifetime
+pub const SONAR_DECOY_EMISSION: Duration = Duration::from_secs(6);
+/// pixels from a surface boat's keel within which a landing shell deals damage, falling off linearly
+pub const SHELL_SPLASH_RADIUS: f32 = 30.0;
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum WeaponType {
     Torpedo,
@@ -101,12 +274,38 @@ impl Weapon {
             _ => 3.0 // for now
         }
     }
+    /// the torpedo a rocket stage releases on splashing down, see [`Staging`](crate::Staging)
+    pub fn payload(&self) -> Option<Weapon> {
+        match self {
+            Self::Vodopad => Some(Self::Type82R),
+            Self::Asroc => Some(Self::Mark54),
+            _ => None,
+        }
+    }
+    /// damage dealt to [`Health`](crate::primitives::Health) on impact, scaled by length (or caliber for shells)
+    pub fn damage(&self) -> f32 {
+        match self.weapon_type() {
+            WeaponType::Torpedo | WeaponType::RocketTorpedo => self.size().x * 3.0,
+            WeaponType::Missle => self.size().x * 2.0,
+            WeaponType::Rocket => self.size().x * 4.0,
+            WeaponType::Shell => self.size().y * 200.0,
+            // at the centre of the blast, see [`DEPTH_CHARGE_BLAST_RADIUS`]
+            WeaponType::DepthCharge => 40.0,
+            WeaponType::AntiAir | WeaponType::SonarDecoy => 0.0,
+        }
+    }
 }
 
 impl WeaponType {
     // todo make a marker component on static weapons for perf
-    /// we're not moving static weapons for now
+    /// static weapons don't move horizontally, depth charges only sink
     pub fn is_static(&self) -> bool {
         matches!(self, Self::DepthCharge)
     }
+    /// whether the weapon explodes when touching a boat
+    ///
+    /// rocket torpedoes and shells fly over boats, until their payload is released or they land
+    pub fn hits_on_contact(&self) -> bool {
+        !matches!(self, Self::DepthCharge | Self::AntiAir | Self::SonarDecoy | Self::RocketTorpedo | Self::Shell)
+    }
 }
\ No newline at end of file
@@ -30,7 +30,8 @@ pub struct WorldPlugin;
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
         #[cfg(feature = "server")]
-        app.add_systems(Startup, spawn_worldsize)
+        app.init_resource::<WorldMin>()
+            .add_systems(Startup, spawn_worldsize)
             .add_observer(on_new_client)
             .add_observer(on_client_disconnected);
 
@@ -51,20 +52,24 @@ impl Plugin for WorldPlugin {
 pub struct WorldSize {
     current_expand: u32,
     player_num: u32,
+    /// size with 0 players
+    min: Vec2,
     /// avoid performance penalty
     computed: Vec2
 }
 
 impl WorldSize {
-    const WORLD_MIN: Vec2 = vec2(3000.0, 1500.0);
+    /// pixels, unless the server configures [`WorldMin`]
+    pub const DEFAULT_MIN: Vec2 = vec2(3000.0, 1500.0);
     const WORLD_EXPAND: Vec2 = Vec2::splat(500.0);
 
     /// 0 players
-    fn new() -> Self {
+    fn new(min: Vec2) -> Self {
         WorldSize {
             current_expand: get_multiplayer_by_player_num(0),
             player_num: 0,
-            computed: get_map_size(0, Self::WORLD_MIN, Self::WORLD_EXPAND),
+            min,
+            computed: get_map_size(0, min, Self::WORLD_EXPAND),
         }
     }
     pub fn player_num(&self) -> u32 {
@@ -81,7 +86,19 @@ impl WorldSize {
 
 impl Default for WorldSize {
     fn default() -> Self {
-        Self::new()
+        Self::new(Self::DEFAULT_MIN)
+    }
+}
+
+/// pixels, the size of the world with 0 players, see [`WorldSize`]
+#[cfg(feature = "server")]
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct WorldMin(pub Vec2);
+
+#[cfg(feature = "server")]
+impl Default for WorldMin {
+    fn default() -> Self {
+        Self(WorldSize::DEFAULT_MIN)
     }
 }
 
@@ -169,7 +186,7 @@ impl WorldSize {
 fn add_player(&mut self) {
     self.player_num += 1;
     self.current_expand = get_multiplayer_by_player_num(self.player_num);
-    self.computed = get_map_size(self.player_num, Self::WORLD_MIN, Self::WORLD_EXPAND)
+    self.computed = get_map_size(self.player_num, self.min, Self::WORLD_EXPAND)
 }
 fn remove_player(&mut self) -> Result<(), ZeroPlayerLeft> {
     if self.player_num == 0 {
@@ -177,15 +194,15 @@ fn remove_player(&mut self) -> Result<(), ZeroPlayerLeft> {
     }
     self.player_num -= 1;
     self.current_expand = get_multiplayer_by_player_num(self.player_num);
-    self.computed = get_map_size(self.player_num, Self::WORLD_MIN, Self::WORLD_EXPAND);
+    self.computed = get_map_size(self.player_num, self.min, Self::WORLD_EXPAND);
 
     Ok(())
 }
 }
 
-pub fn spawn_worldsize(mut commands: Commands) {
+pub fn spawn_worldsize(mut commands: Commands, min: Res<WorldMin>) {
     commands.spawn((
-        WorldSize::new(),
+        WorldSize::new(min.0),
         Replicate::to_clients(NetworkTarget::All)
     ));
 }
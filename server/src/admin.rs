@@ -0,0 +1,213 @@
+//! what the admin API in [`net`](crate::net) does to the world
+//!
+//! HTTP handlers run on the actix thread, so they send an [`AdminCommand`] down a channel
+//! that [`run_admin_commands`] drains every frame, answering on the command's oneshot if it has one
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use common::{Boat, BoatClientId};
+use common::primitives::{CustomTransform, PlayerStats};
+use common::protocol::{Announcement, Nickname, SendToClientOrdered};
+use lightyear::prelude::{server::ClientOf, *};
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::config::ServerConfig;
+
+pub(crate) struct AdminPlugin;
+
+impl Plugin for AdminPlugin {
+    fn build(&self, app: &mut App) {
+        // a frame apart, so the disconnects are sent before exiting
+        app.add_systems(Update, (exit_after_disconnecting.run_if(resource_exists::<ShuttingDown>), run_admin_commands).chain())
+            .add_observer(kick_banned);
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum AdminCommand {
+    ListPlayers(oneshot::Sender<Vec<PlayerInfo>>),
+    /// answers whether the client was connected
+    Kick(u64, oneshot::Sender<bool>),
+    /// kicks the client and bans its IP, answers whether the client was connected
+    Ban(u64, oneshot::Sender<bool>),
+    /// answers why it couldn't be sent
+    Announce(String, oneshot::Sender<Result<(), String>>),
+    /// takes effect from the next rig, see [`ServerConfig::set_rig_rate`]
+    SetRigRate(RigRate, oneshot::Sender<Result<(), String>>),
+    /// disconnects everyone and exits once they're gone, see [`ShuttingDown`]
+    Shutdown,
+}
+
+/// how long a shutdown waits for clients to disconnect
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// inserted on [`AdminCommand::Shutdown`]
+#[derive(Resource, Debug)]
+struct ShuttingDown {
+    /// elapsed [`Time<Real>`], exits even if some clients are still connected
+    deadline: Duration,
+}
+
+/// anything unset stays as is
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RigRate {
+    /// seconds
+    pub rig_interval_min: Option<f64>,
+    /// seconds
+    pub rig_interval_max: Option<f64>,
+    pub point_spawn_chance: Option<f64>,
+}
+
+/// one connected client, boat fields are None between sinking and respawning
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct PlayerInfo {
+    pub id: u64,
+    pub nickname: Option<String>,
+    pub boat: Option<Boat>,
+    pub score: Option<u32>,
+    pub position: Option<[f32; 2]>,
+}
+
+/// the actix side of the channel
+pub(crate) type AdminSender = Sender<AdminCommand>;
+
+#[derive(Resource)]
+pub(crate) struct AdminReceiver(Mutex<Receiver<AdminCommand>>);
+
+pub(crate) fn channel() -> (AdminSender, AdminReceiver) {
+    let (tx, rx) = mpsc::channel();
+    (tx, AdminReceiver(Mutex::new(rx)))
+}
+
+/// IPs refused by `/join` and kicked on connecting, shared with the actix thread
+///
+/// client ids are handed out fresh on every join, so they can't be banned
+#[derive(Resource, Debug, Clone, Default)]
+pub(crate) struct Bans(Arc<RwLock<HashSet<IpAddr>>>);
+
+impl Bans {
+    pub(crate) fn is_banned(&self, ip: IpAddr) -> bool {
+        self.0.read().unwrap().contains(&ip)
+    }
+    fn ban(&self, ip: IpAddr) {
+        self.0.write().unwrap().insert(ip);
+    }
+}
+
+fn run_admin_commands(
+    rx: Res<AdminReceiver>,
+    clients: Query<(&RemoteId, Option<&PeerAddr>, Entity), With<ClientOf>>,
+    boats: Query<(&BoatClientId, &Nickname, &Boat, &PlayerStats, &CustomTransform)>,
+    mut config: ResMut<ServerConfig>,
+    bans: Res<Bans>,
+    mut sender: ServerMultiMessageSender,
+    server: Single<&Server>,
+    shutting_down: Option<Res<ShuttingDown>>,
+    time: Res<Time<Real>>,
+    mut commands: Commands,
+) {
+    let find = |id: u64| clients.iter().find(|(RemoteId(client_id), ..)| *client_id == PeerId::Netcode(id));
+
+    for command in rx.0.lock().unwrap().try_iter() {
+        info!("Admin: {command:?}");
+        match command {
+            AdminCommand::ListPlayers(reply) => {
+                let players = clients
+                    .iter()
+                    .filter_map(|(&RemoteId(client_id), ..)| {
+                        let PeerId::Netcode(id) = client_id else {
+                            return None;
+                        };
+                        let boat = boats.iter().find(|(owner, ..)| owner.0 == client_id);
+                        Some(PlayerInfo {
+                            id,
+                            nickname: boat.map(|(_, name, ..)| name.0.clone()),
+                            boat: boat.map(|(_, _, &boat, ..)| boat),
+                            score: boat.map(|(.., stats, _)| stats.score()),
+                            position: boat.map(|(.., custom)| custom.position.0.to_array()),
+                        })
+                    })
+                    .collect();
+                let _ = reply.send(players);
+            }
+            AdminCommand::Kick(id, reply) => {
+                let found = find(id);
+                if let Some((.., client)) = found {
+                    commands.trigger(Disconnect { entity: client });
+                }
+                let _ = reply.send(found.is_some());
+            }
+            AdminCommand::Ban(id, reply) => {
+                let found = find(id);
+                if let Some((_, addr, client)) = found {
+                    match addr {
+                        Some(addr) => bans.ban(addr.0.ip()),
+                        None => warn!("Client {id} has no address to ban, only kicking"),
+                    }
+                    commands.trigger(Disconnect { entity: client });
+                }
+                let _ = reply.send(found.is_some());
+            }
+            AdminCommand::Announce(text, reply) => {
+                let result = sender
+                    .send::<_, SendToClientOrdered>(&Announcement(text), &server, &NetworkTarget::All)
+                    .map_err(|e| e.to_string());
+                if let Err(e) = &result {
+                    error!("Couldn't send the announcement: {e}");
+                }
+                let _ = reply.send(result);
+            }
+            AdminCommand::SetRigRate(rate, reply) => {
+                let result = config
+                    .set_rig_rate(rate.rig_interval_min, rate.rig_interval_max, rate.point_spawn_chance)
+                    .map_err(|e| e.to_string());
+                let _ = reply.send(result);
+            }
+            AdminCommand::Shutdown => {
+                for (.., client) in &clients {
+                    commands.trigger(Disconnect { entity: client });
+                }
+                if shutting_down.is_none() {
+                    commands.insert_resource(ShuttingDown { deadline: time.elapsed() + SHUTDOWN_TIMEOUT });
+                }
+            }
+        }
+    }
+}
+
+fn exit_after_disconnecting(
+    shutting_down: Res<ShuttingDown>,
+    clients: Query<(), With<ClientOf>>,
+    time: Res<Time<Real>>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    if clients.is_empty() {
+        exit.write(AppExit::Success);
+    } else if time.elapsed() >= shutting_down.deadline {
+        warn!("{} clients still connected, exiting anyway", clients.iter().count());
+        exit.write(AppExit::Success);
+    }
+}
+
+/// they may have joined before being banned, or through another client id
+fn kick_banned(
+    trigger: On<Add, Connected>,
+    clients: Query<(&RemoteId, &PeerAddr), With<ClientOf>>,
+    bans: Res<Bans>,
+    mut commands: Commands,
+) {
+    let Ok((RemoteId(client_id), addr)) = clients.get(trigger.entity) else {
+        return;
+    };
+    if bans.is_banned(addr.0.ip()) {
+        info!("Kicking banned client {client_id} from {}", addr.0);
+        commands.trigger(Disconnect { entity: trigger.entity });
+    }
+}
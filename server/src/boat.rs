@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use common::{
+    Boat, BoatClientId, OCEAN_SURFACE, Rammed, Sinking, SpeedViolation, Turrets,
+    primitives::{CustomTransform, Health, Level, PlayerStats, Position, Speed, WeaponCounter},
+    protocol::{AimTurrets, BoatDestroyed, DeathReason, DiveIntent, EntityOnServer, Move, PingSonar, RespawnRequest, Rotate, SendToClient},
+};
+use lightyear::prelude::{input::native::ActionState, server::ClientOf, *};
+use rand::seq::IndexedRandom;
+
+use crate::nickname;
+use crate::weapon::LastReloaded;
+
+/// how long a [`Sinking`] boat stays in the world before being despawned
+const SINK_DURATION: Duration = Duration::from_secs(3);
+/// a client is reported every time it's caught speeding this many more times, see [`SpeedViolation`]
+const REPORTED_SPEED_VIOLATIONS: u32 = 5;
+
+/// lifecycle of the main boat entity: sinking on zero health and respawning on request
+pub(crate) struct BoatPlugin;
+
+impl Plugin for BoatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(on_destroyed)
+            .add_observer(on_rammed)
+            .add_observer(report_speed_violations)
+            .add_systems(FixedUpdate, (despawn_sunk, recv_respawn).chain());
+    }
+}
+
+/// triggered once when a boat's [`Health`] reaches zero
+#[derive(Debug, EntityEvent)]
+pub(crate) struct Destroyed {
+    pub entity: Entity,
+    pub reason: DeathReason,
+}
+
+/// when the boat started [`Sinking`], elapsed [`Time<Fixed>`], server only
+#[derive(Debug, Component)]
+struct SinkingSince(Duration);
+
+/// a random level 1 boat, for new players and respawns
+pub(crate) fn starting_boat() -> Boat {
+    let candidates = Level::One.avaliable_boats().collect::<Vec<_>>();
+    *candidates.choose(&mut rand::rng()).expect("Every level has boats")
+}
+
+/// spawns the main boat entity controlled by the `client` entity (with [`ClientOf`])
+pub(crate) fn spawn_boat(commands: &mut Commands, boat: Boat, client_id: PeerId, client: Entity) -> Entity {
+    let position = vec2(
+        rand::random_range(-200.0..200.0),
+        rand::random_range(-200.0..200.0),
+    );
+
+    info!("Spawning {boat:?} for client {client_id}");
+
+    let mut entity_commands = commands.spawn((
+        CustomTransform {
+            position: Position(position),
+            ..CustomTransform::default()
+        },
+        boat,
+        WeaponCounter::from_boat(&boat),
+        LastReloaded(boat.armanents().keys().map(|k| (*k, None)).collect()),
+        OCEAN_SURFACE,
+        PlayerStats::new(0),
+        Health::new(boat.max_health()),
+        Turrets::new(boat),
+
+        BoatClientId(client_id),
+        nickname::claim(client_id).unwrap_or_else(|| nickname::fallback(client_id)),
+
+        Replicate::to_clients(NetworkTarget::All),
+        PredictionTarget::to_clients(NetworkTarget::Single(client_id)),
+        InterpolationTarget::to_clients(NetworkTarget::AllExceptSingle(client_id)),
+
+        ActionState::<Rotate>::default(),
+        ActionState::<Move>::default(),
+        ActionState::<DiveIntent>::default(),
+        ActionState::<PingSonar>::default(),
+        ActionState::<AimTurrets>::default(),
+
+        ControlledBy {
+            owner: client,
+            lifetime: Lifetime::SessionBased
+        },
+    ));
+    entity_commands.insert(EntityOnServer(entity_commands.id().to_bits()));
+    entity_commands.id()
+}
+
+/// stops the boat and tells the owner why it sank
+fn on_destroyed(
+    trigger: On<Destroyed>,
+    mut boats: Query<(&mut CustomTransform, &PlayerStats, &BoatClientId), Without<Sinking>>,
+    time: Res<Time<Fixed>>,
+    mut commands: Commands,
+    mut sender: ServerMultiMessageSender,
+    server: Single<&Server>,
+) {
+    let Ok((mut custom, stats, client_id)) = boats.get_mut(trigger.entity) else {
+        return;  // already sinking
+    };
+    info!("Client {:?}'s boat was destroyed: {:?}", client_id.0, trigger.reason);
+
+    custom.speed = Speed::ZERO;
+    commands.entity(trigger.entity).insert((Sinking, SinkingSince(time.elapsed())));
+
+    sender.send::<_, SendToClient>(
+        &BoatDestroyed {
+            reason: trigger.reason,
+            score: stats.score()
+        },
+        &server,
+        &NetworkTarget::Single(client_id.0)
+    ).unwrap();
+}
+
+/// only logged for now, kicking would go here
+fn report_speed_violations(trigger: On<SpeedViolation>, clients: Query<&RemoteId, With<ClientOf>>) {
+    if trigger.violations % REPORTED_SPEED_VIOLATIONS != 0 {
+        return;
+    }
+    let Ok(RemoteId(client_id)) = clients.get(trigger.entity) else {
+        return;
+    };
+    warn!("Client {client_id} was caught speeding {} times", trigger.violations);
+}
+
+fn on_rammed(trigger: On<Rammed>, mut boats: Query<&mut Health, Without<Sinking>>, mut commands: Commands) {
+    let Ok(mut health) = boats.get_mut(trigger.entity) else {
+        return;
+    };
+    // may have been rammed more than once this tick
+    if !health.is_dead() && health.damage(trigger.damage) {
+        commands.trigger(Destroyed {
+            entity: trigger.entity,
+            reason: trigger.reason,
+        });
+    }
+}
+
+fn despawn_sunk(mut commands: Commands, boats: Query<(&SinkingSince, Entity)>, time: Res<Time<Fixed>>) {
+    for (since, id) in boats {
+        if time.elapsed() - since.0 > SINK_DURATION {
+            commands.entity(id).despawn();
+        }
+    }
+}
+
+/// spawns a random level 1 boat if the client has no boat left afloat
+fn recv_respawn(
+    rxs: Query<(&mut MessageReceiver<RespawnRequest>, &RemoteId, Entity), With<ClientOf>>,
+    boats: Query<(&ControlledBy, Has<Sinking>, Entity), With<Boat>>,
+    mut commands: Commands,
+) {
+    for (mut rx, &RemoteId(client_id), client) in rxs {
+        for RespawnRequest in rx.receive() {
+            let owned = || boats.iter().filter(|(controlled_by, ..)| controlled_by.owner == client);
+            if owned().any(|(_, sinking, _)| !sinking) {
+                info!("Client {client_id} requested a respawn while still afloat");
+                continue;
+            }
+            // the client expects a single controlled boat
+            for (.., id) in owned() {
+                commands.entity(id).despawn();
+            }
+
+            spawn_boat(&mut commands, starting_boat(), client_id, client);
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! server settings, layered from lowest to highest priority:
+//! 1. defaults, see [`ServerConfig::default`]
+//! 2. a TOML file, `server.toml` if present or `--config <path>`
+//! 3. `MK73_*` environment variables
+//! 4. CLI flags, see `--help`
+//!
+//! every layer uses the same names, e.g. `tick_rate = 30.0`, `MK73_TICK_RATE=30` and `--tick-rate 30`
+
+use std::fmt;
+use std::net::SocketAddr;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::prelude::*;
+use clap::Parser;
+use common::{PROTOCOL_ID, SERVER_ADDR, TCP_ADDR, WorldSize};
+use lightyear::netcode::Key;
+use serde::Deserialize;
+
+/// read if `--config` isn't given, relative to where the server is launched
+const DEFAULT_CONFIG_PATH: &str = "server.toml";
+
+/// every setting after layering and validation, inserted before any plugin is built
+#[derive(Resource, Debug, Clone)]
+pub(crate) struct ServerConfig {
+    /// game traffic (WebTransport), bound by the server
+    pub server_addr: SocketAddr,
+    /// where clients reach `server_addr`, put in connect tokens and sent by `/join`
+    pub public_addr: SocketAddr,
+    /// HTTP backend serving `/join`
+    pub http_addr: SocketAddr,
+    /// clients of another protocol id are rejected
+    pub protocol_id: u64,
+    /// Hz, of the schedule loop
+    pub tick_rate: f64,
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    /// pixels, the world only grows from there with more players
+    pub world_min: Vec2,
+    /// between two rigs spawning
+    pub rig_interval: Range<Duration>,
+    /// points a rig spawns before it stops, `max` exclusive
+    pub rig_points: Range<u16>,
+    /// probability within 0..=1 of a rig spawning a point every tick
+    pub point_spawn_chance: f64,
+    /// probability within 0..=1 of a sonar decoy luring a torpedo
+    pub spoof_chance: f64,
+    /// JSON file overriding boat and weapon stats, see [`common::definitions`]
+    pub definitions: Option<PathBuf>,
+    /// words rejected in nicknames, see [`crate::nickname::load_profanity`]
+    pub profanity: PathBuf,
+    /// whether `profanity` was set rather than defaulted, only then a missing file is an error
+    pub profanity_required: bool,
+    /// signs connect tokens, random unless set
+    pub private_key: Key,
+    /// bearer token of the admin API, which is disabled without one
+    pub admin_token: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            server_addr: SERVER_ADDR,
+            public_addr: SERVER_ADDR,
+            http_addr: TCP_ADDR,
+            protocol_id: PROTOCOL_ID,
+            tick_rate: 60.0,
+            cert: PathBuf::from("../cert/cert.pem"),
+            key: PathBuf::from("../cert/key.pem"),
+            world_min: WorldSize::DEFAULT_MIN,
+            rig_interval: Duration::from_secs(10)..Duration::from_secs(120),
+            rig_points: 30..40,
+            // more points to play with while debugging
+            point_spawn_chance: if cfg!(debug_assertions) { 1.0 / 5.0 } else { 1.0 / 41.0 },
+            spoof_chance: 0.5,
+            definitions: None,
+            profanity: PathBuf::from("profanity.txt"),
+            profanity_required: false,
+            private_key: rand::random(),
+            admin_token: None,
+        }
+    }
+}
+
+/// one layer of [`ServerConfig`], anything unset falls through to the layer below
+///
+/// clap reads both the CLI flags and the environment variables
+#[derive(Parser, Deserialize, Debug, Default)]
+#[command(about = "mk73 game server")]
+#[serde(default, deny_unknown_fields)]
+struct Overrides {
+    /// TOML file with any of the settings below, `server.toml` if present
+    #[arg(long, env = "MK73_CONFIG")]
+    #[serde(skip)]
+    config: Option<PathBuf>,
+    /// game traffic (WebTransport), e.g. 0.0.0.0:8000
+    #[arg(long, env = "MK73_SERVER_ADDR")]
+    server_addr: Option<SocketAddr>,
+    /// where clients reach the game traffic, e.g. 203.0.113.7:8000, `server_addr` if unset
+    #[arg(long, env = "MK73_PUBLIC_ADDR")]
+    public_addr: Option<SocketAddr>,
+    /// HTTP backend serving `/join`, e.g. 0.0.0.0:9000
+    #[arg(long, env = "MK73_HTTP_ADDR")]
+    http_addr: Option<SocketAddr>,
+    #[arg(long, env = "MK73_PROTOCOL_ID")]
+    protocol_id: Option<u64>,
+    /// Hz
+    #[arg(long, env = "MK73_TICK_RATE")]
+    tick_rate: Option<f64>,
+    /// PEM certificate for WebTransport
+    #[arg(long, env = "MK73_CERT")]
+    cert: Option<PathBuf>,
+    /// PEM private key of the certificate
+    #[arg(long, env = "MK73_KEY")]
+    key: Option<PathBuf>,
+    /// pixels
+    #[arg(long, env = "MK73_WORLD_MIN_WIDTH")]
+    world_min_width: Option<f32>,
+    /// pixels
+    #[arg(long, env = "MK73_WORLD_MIN_HEIGHT")]
+    world_min_height: Option<f32>,
+    /// seconds
+    #[arg(long, env = "MK73_RIG_INTERVAL_MIN")]
+    rig_interval_min: Option<f64>,
+    /// seconds, exclusive
+    #[arg(long, env = "MK73_RIG_INTERVAL_MAX")]
+    rig_interval_max: Option<f64>,
+    #[arg(long, env = "MK73_RIG_POINTS_MIN")]
+    rig_points_min: Option<u16>,
+    /// exclusive
+    #[arg(long, env = "MK73_RIG_POINTS_MAX")]
+    rig_points_max: Option<u16>,
+    /// probability within 0..=1, every tick
+    #[arg(long, env = "MK73_POINT_SPAWN_CHANCE")]
+    point_spawn_chance: Option<f64>,
+    /// probability within 0..=1
+    #[arg(long, env = "MK73_SPOOF_CHANCE")]
+    spoof_chance: Option<f64>,
+    /// JSON file overriding boat and weapon stats
+    #[arg(long, env = "MK73_DEFINITIONS")]
+    definitions: Option<PathBuf>,
+    /// one word per line
+    #[arg(long, env = "MK73_PROFANITY")]
+    profanity: Option<PathBuf>,
+    /// 64 hex digits
+    #[arg(long, env = "MK73_PRIVATE_KEY", hide_env_values = true)]
+    private_key: Option<String>,
+    /// bearer token of the admin API under `/admin`, disabled if unset
+    #[arg(long, env = "MK73_ADMIN_TOKEN", hide_env_values = true)]
+    admin_token: Option<String>,
+}
+
+#[derive(Debug)]
+pub(crate) enum ConfigError {
+    Read(PathBuf, std::io::Error),
+    Toml(PathBuf, toml::de::Error),
+    Invalid { setting: &'static str, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(path, e) => write!(f, "couldn't read {}: {e}", path.display()),
+            Self::Toml(path, e) => write!(f, "{}: {e}", path.display()),
+            Self::Invalid { setting, reason } => write!(f, "{setting}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn invalid(setting: &'static str, reason: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid { setting, reason: reason.into() }
+}
+
+impl ServerConfig {
+    /// exits with clap's usage message on unparsable flags or environment variables
+    pub(crate) fn load() -> Result<Self, ConfigError> {
+        let cli = Overrides::parse();
+        let file = match &cli.config {
+            Some(path) => Some(read_file(path)?),
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => Some(read_file(Path::new(DEFAULT_CONFIG_PATH))?),
+            None => None,
+        };
+
+        let mut merged = Overrides::default();
+        for layer in file.into_iter().chain([cli]) {
+            merged.apply(layer);
+        }
+        Self::resolve(merged)
+    }
+
+    /// of the schedule loop
+    pub(crate) fn tick(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.tick_rate)
+    }
+
+    /// while running, see the admin API. Nothing changes if any value is invalid
+    pub(crate) fn set_rig_rate(
+        &mut self,
+        rig_interval_min: Option<f64>,
+        rig_interval_max: Option<f64>,
+        point_spawn_chance: Option<f64>,
+    ) -> Result<(), ConfigError> {
+        let interval = rig_interval(
+            rig_interval_min.unwrap_or(self.rig_interval.start.as_secs_f64()),
+            rig_interval_max.unwrap_or(self.rig_interval.end.as_secs_f64()),
+        )?;
+        let chance = probability("point_spawn_chance", point_spawn_chance.unwrap_or(self.point_spawn_chance))?;
+        self.rig_interval = interval;
+        self.point_spawn_chance = chance;
+        Ok(())
+    }
+
+    fn resolve(layer: Overrides) -> Result<Self, ConfigError> {
+        let default = Self::default();
+
+        let tick_rate = layer.tick_rate.unwrap_or(default.tick_rate);
+        if !(tick_rate.is_finite() && tick_rate > 0.0) {
+            return Err(invalid("tick_rate", format!("must be a positive number of Hz, got {tick_rate}")));
+        }
+
+        let cert = layer.cert.unwrap_or(default.cert);
+        let key = layer.key.unwrap_or(default.key);
+        for (setting, path) in [("cert", &cert), ("key", &key)] {
+            if !path.is_file() {
+                return Err(invalid(setting, format!("{} isn't a file", path.display())));
+            }
+        }
+
+        let server_addr = layer.server_addr.unwrap_or(default.server_addr);
+        let public_addr = layer.public_addr.unwrap_or(server_addr);
+        if public_addr.ip().is_unspecified() {
+            return Err(invalid("public_addr", format!("clients can't connect to {public_addr}, set it when binding to every interface")));
+        }
+
+        let world_min = vec2(
+            layer.world_min_width.unwrap_or(default.world_min.x),
+            layer.world_min_height.unwrap_or(default.world_min.y),
+        );
+        if !(world_min.is_finite() && world_min.cmpgt(Vec2::ZERO).all()) {
+            return Err(invalid("world_min_width/world_min_height", format!("must be positive, got {world_min}")));
+        }
+
+        let rig_interval = rig_interval(
+            layer.rig_interval_min.unwrap_or(default.rig_interval.start.as_secs_f64()),
+            layer.rig_interval_max.unwrap_or(default.rig_interval.end.as_secs_f64()),
+        )?;
+
+        let rig_points = layer.rig_points_min.unwrap_or(default.rig_points.start)..layer.rig_points_max.unwrap_or(default.rig_points.end);
+        if rig_points.is_empty() {
+            return Err(invalid("rig_points_min/rig_points_max", format!("min must be below max, got {rig_points:?}")));
+        }
+
+        let point_spawn_chance = probability("point_spawn_chance", layer.point_spawn_chance.unwrap_or(default.point_spawn_chance))?;
+        let spoof_chance = probability("spoof_chance", layer.spoof_chance.unwrap_or(default.spoof_chance))?;
+
+        let private_key = match layer.private_key {
+            Some(hex) => parse_private_key(&hex).map_err(|reason| invalid("private_key", reason))?,
+            None => {
+                eprintln!("No private key set, connect tokens only work until restarting");
+                default.private_key
+            }
+        };
+
+        Ok(Self {
+            server_addr,
+            public_addr,
+            http_addr: layer.http_addr.unwrap_or(default.http_addr),
+            protocol_id: layer.protocol_id.unwrap_or(default.protocol_id),
+            tick_rate,
+            cert,
+            key,
+            world_min,
+            rig_interval,
+            rig_points,
+            point_spawn_chance,
+            spoof_chance,
+            definitions: layer.definitions,
+            profanity_required: layer.profanity.is_some(),
+            profanity: layer.profanity.unwrap_or(default.profanity),
+            private_key,
+            admin_token: match layer.admin_token {
+                Some(token) if token.trim().is_empty() => return Err(invalid("admin_token", "can't be empty")),
+                token => token,
+            },
+        })
+    }
+}
+
+impl Overrides {
+    /// `other` wins wherever it's set
+    fn apply(&mut self, other: Self) {
+        macro_rules! apply {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() { self.$field = other.$field; })*
+            };
+        }
+        apply!(
+            config, server_addr, public_addr, http_addr, protocol_id, tick_rate, cert, key, world_min_width, world_min_height,
+            rig_interval_min, rig_interval_max, rig_points_min, rig_points_max, point_spawn_chance, spoof_chance,
+            definitions, profanity, private_key, admin_token
+        );
+    }
+}
+
+fn read_file(path: &Path) -> Result<Overrides, ConfigError> {
+    let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_owned(), e))?;
+    toml::from_str(&text).map_err(|e| ConfigError::Toml(path.to_owned(), e))
+}
+
+/// seconds
+fn rig_interval(min: f64, max: f64) -> Result<Range<Duration>, ConfigError> {
+    let interval = Duration::try_from_secs_f64(min)
+        .and_then(|min| Ok(min..Duration::try_from_secs_f64(max)?))
+        .map_err(|e| invalid("rig_interval_min/rig_interval_max", e.to_string()))?;
+    if interval.is_empty() {
+        return Err(invalid("rig_interval_min/rig_interval_max", format!("min must be below max, got {interval:?}")));
+    }
+    Ok(interval)
+}
+
+fn probability(setting: &'static str, value: f64) -> Result<f64, ConfigError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(invalid(setting, format!("must be a probability within 0..=1, got {value}")))
+    }
+}
+
+/// 64 hex digits
+fn parse_private_key(hex: &str) -> Result<Key, String> {
+    let hex = hex.trim();
+    if hex.len() != 64 {
+        return Err(format!("expected 64 hex digits, got {}", hex.len()));
+    }
+    let mut key = Key::default();
+    for (byte, digits) in key.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let digits = std::str::from_utf8(digits).map_err(|e| e.to_string())?;
+        *byte = u8::from_str_radix(digits, 16).map_err(|e| format!("{digits:?}: {e}"))?;
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// which setting [`ServerConfig::resolve`] rejected, None if it didn't
+    fn rejected(layer: Overrides) -> Option<&'static str> {
+        match ServerConfig::resolve(layer) {
+            Ok(_) => None,
+            Err(ConfigError::Invalid { setting, .. }) => Some(setting),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        // the default cert and key are the repo's, relative to the server crate where tests run
+        let config = ServerConfig::resolve(Overrides::default()).unwrap();
+        assert_eq!(config.public_addr, config.server_addr);
+
+        assert_eq!(rejected(Overrides { rig_interval_min: Some(30.0), rig_interval_max: Some(30.0), ..default() }), Some("rig_interval_min/rig_interval_max"));
+        assert_eq!(rejected(Overrides { rig_interval_min: Some(-1.0), ..default() }), Some("rig_interval_min/rig_interval_max"));
+
+        assert_eq!(rejected(Overrides { spoof_chance: Some(1.5), ..default() }), Some("spoof_chance"));
+        assert_eq!(rejected(Overrides { point_spawn_chance: Some(f64::NAN), ..default() }), Some("point_spawn_chance"));
+
+        assert_eq!(rejected(Overrides { private_key: Some("abc".to_owned()), ..default() }), Some("private_key"));
+        assert_eq!(rejected(Overrides { private_key: Some("zz".repeat(32)), ..default() }), Some("private_key"));
+        assert_eq!(rejected(Overrides { private_key: Some("0f".repeat(32)), ..default() }), None);
+
+        assert_eq!(rejected(Overrides { tick_rate: Some(0.0), ..default() }), Some("tick_rate"));
+        assert_eq!(rejected(Overrides { tick_rate: Some(f64::NAN), ..default() }), Some("tick_rate"));
+    }
+
+    #[test]
+    fn test_public_addr() {
+        let everywhere: SocketAddr = "0.0.0.0:8000".parse().unwrap();
+        let public: SocketAddr = "203.0.113.7:8000".parse().unwrap();
+
+        assert_eq!(rejected(Overrides { server_addr: Some(everywhere), ..default() }), Some("public_addr"));
+        let config = ServerConfig::resolve(Overrides { server_addr: Some(everywhere), public_addr: Some(public), ..default() }).unwrap();
+        assert_eq!((config.server_addr, config.public_addr), (everywhere, public));
+    }
+}
@@ -0,0 +1,45 @@
+//! rebuilds the [`SpatialGrid`]s shared by the server's systems, see [`common::grid`]
+
+use bevy::prelude::*;
+use common::grid::SpatialGrid;
+use common::primitives::{CustomTransform, Point};
+use common::protocol::PointTransform;
+use common::{Boat, Weapon};
+
+/// pixels a boat may have moved since the grids were rebuilt this tick, added to query radii that need precision
+pub(crate) const GRID_SLACK: f32 = 5.0;
+
+pub(crate) struct GridPlugin;
+
+impl Plugin for GridPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialGrid<Boat>>()
+            .init_resource::<SpatialGrid<Weapon>>()
+            .init_resource::<SpatialGrid<Point>>()
+            // before anything in `FixedUpdate` queries them, anything spawned this tick is found from the next one
+            .add_systems(FixedPreUpdate, rebuild_grids);
+    }
+}
+
+/// sinking boats included, systems filter them out themselves
+fn rebuild_grids(
+    mut boat_grid: ResMut<SpatialGrid<Boat>>,
+    mut weapon_grid: ResMut<SpatialGrid<Weapon>>,
+    mut point_grid: ResMut<SpatialGrid<Point>>,
+    boats: Query<(&CustomTransform, Entity), With<Boat>>,
+    weapons: Query<(&Transform, Entity), With<Weapon>>,
+    points: Query<(&PointTransform, Entity), With<Point>>,
+) {
+    boat_grid.clear();
+    for (custom, id) in boats {
+        boat_grid.insert(id, custom.position.0);
+    }
+    weapon_grid.clear();
+    for (transform, id) in weapons {
+        weapon_grid.insert(id, transform.translation.xy());
+    }
+    point_grid.clear();
+    for (point, id) in points {
+        point_grid.insert(id, point.position);
+    }
+}
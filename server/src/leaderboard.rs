@@ -0,0 +1,78 @@
+//! ranks every boat by [`PlayerStats::score`] and periodically sends each client the top of it
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use common::primitives::PlayerStats;
+use common::protocol::{Leaderboard, LeaderboardEntry, Nickname, SendLeaderboard};
+use common::{Boat, BoatClientId};
+use lightyear::prelude::{server::ClientOf, *};
+
+/// rows in [`Leaderboard::top`]
+const LEADERBOARD_SIZE: usize = 10;
+/// how often the leaderboard is sent, it doesn't need to be exact
+const LEADERBOARD_INTERVAL: Duration = Duration::from_secs(1);
+
+pub(crate) struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Ranking>()
+            .insert_resource(LeaderboardTimer(Timer::new(LEADERBOARD_INTERVAL, TimerMode::Repeating)))
+            .add_systems(FixedUpdate, (update_ranking, send_leaderboard).chain());
+    }
+}
+
+/// every boat from the highest score, only re-sorted when a score changes
+#[derive(Resource, Debug, Default)]
+struct Ranking(Vec<Entity>);
+
+#[derive(Resource, Deref, DerefMut)]
+struct LeaderboardTimer(Timer);
+
+/// anything changing [`PlayerStats`] (e.g. [`PlayerStats::add_to_score`]) or a boat leaving re-sorts the ranking
+fn update_ranking(
+    changed: Query<(), Changed<PlayerStats>>,
+    mut removed: RemovedComponents<PlayerStats>,
+    boats: Query<(&PlayerStats, Entity)>,
+    mut ranking: ResMut<Ranking>,
+) {
+    if changed.is_empty() && removed.read().count() == 0 {
+        return;
+    }
+    let mut sorted: Vec<(u32, Entity)> = boats.iter().map(|(stats, id)| (stats.score(), id)).collect();
+    // ties broken by entity so the order doesn't flicker
+    sorted.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    ranking.0 = sorted.into_iter().map(|(_, id)| id).collect();
+}
+
+fn send_leaderboard(
+    mut timer: ResMut<LeaderboardTimer>,
+    time: Res<Time>,
+    ranking: Res<Ranking>,
+    boats: Query<(&PlayerStats, &Boat, &BoatClientId, &Nickname)>,
+    clients: Query<&RemoteId, With<ClientOf>>,
+    mut sender: ServerMultiMessageSender,
+    server: Single<&Server>,
+) {
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    let entry = |id: Entity| {
+        let (stats, &boat, client_id, name) = boats.get(id).ok()?;
+        Some((client_id.0, LeaderboardEntry { name: name.0.clone(), boat, score: stats.score() }))
+    };
+    let rows: Vec<(PeerId, LeaderboardEntry)> = ranking.0.iter().filter_map(|&id| entry(id)).collect();
+    let top: Vec<LeaderboardEntry> = rows.iter().take(LEADERBOARD_SIZE).map(|(_, row)| row.clone()).collect();
+
+    for &RemoteId(client_id) in &clients {
+        let own = rows.iter()
+            .position(|(owner, _)| *owner == client_id)
+            .map(|index| (index as u32 + 1, rows[index].1.clone()));
+        sender.send::<_, SendLeaderboard>(
+            &Leaderboard { top: top.clone(), own },
+            &server,
+            &NetworkTarget::Single(client_id)
+        ).unwrap();
+    }
+}
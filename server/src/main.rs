@@ -1,14 +1,22 @@
+mod admin;
+mod boat;
+mod config;
+mod grid;
+mod leaderboard;
+mod nickname;
 mod oil_rig;
+mod sensor;
 mod weapon;
 mod net;
 
-use std::{sync::{LazyLock, RwLock}, time::{Duration, Instant}};
+use std::sync::RwLock;
 
 #[cfg(not(feature = "gui"))]
 use bevy::app::{ScheduleRunnerPlugin, TerminalCtrlCHandlerPlugin};
 use bevy::{diagnostic::{DiagnosticsPlugin, LogDiagnosticsPlugin}, log::LogPlugin, prelude::*, state::app::StatesPlugin};
 use common::{
-    Boat, BoatClientId, MovementPlugin, OCEAN_SURFACE, PROTOCOL_ID, SERVER_ADDR, UpgradePlugin, WorldPlugin, primitives::{CustomTransform, PlayerStats, Position, WeaponCounter, ZIndex}, protocol::{Move, ProtocolPlugin, Rotate}
+    DivePlugin, MovementPlugin, TurretPlugin, UpgradePlugin, WorldMin, WorldPlugin,
+    definitions::{self, Definitions}, primitives::ZIndex, protocol::{ProtocolPlugin, SendToClientOrdered}
 };
 use lightyear::{
     prelude::input::native::ActionState, webtransport::server::WebTransportServerIo
@@ -20,19 +28,41 @@ use lightyear::{
         *,
     },
 };
-use common::protocol::{EntityOnServer, ZIndexUpdate};
-use crate::{oil_rig::OilRigPlugin, weapon::LastReloaded};
+use crate::{
+    admin::{AdminPlugin, Bans}, boat::BoatPlugin, grid::GridPlugin, leaderboard::LeaderboardPlugin, nickname::NicknamePlugin, oil_rig::OilRigPlugin, sensor::SensorPlugin
+};
+use crate::config::ServerConfig;
 use crate::weapon::WeaponPlugin;
 
-static FPS: LazyLock<Duration> = LazyLock::new(|| Duration::from_secs_f32(1.0 / 60.0));
-
 fn main() {
+    let config = ServerConfig::load().unwrap_or_else(|e| {
+        eprintln!("Invalid configuration: {e}");
+        std::process::exit(2);
+    });
+
+    if let Some(path) = &config.definitions {
+        let loaded = Definitions::load(path).unwrap_or_else(|e| {
+            eprintln!("Invalid definitions in {}: {e}", path.display());
+            std::process::exit(2);
+        });
+        eprintln!("Loaded definitions from {}", path.display());
+        definitions::set_active(loaded).expect("Definitions set before start-up");
+    }
+    match nickname::load_profanity(&config.profanity) {
+        Ok(count) => eprintln!("Loaded {count} profane words from {}", config.profanity.display()),
+        Err(e) if config.profanity_required => {
+            eprintln!("Couldn't read profanity list {}: {e}", config.profanity.display());
+            std::process::exit(2);
+        }
+        Err(e) => eprintln!("Not filtering nicknames, couldn't read {}: {e}", config.profanity.display()),
+    }
+
     let mut app = App::new();
     
     #[cfg(not(feature = "gui"))]
     app.add_plugins((
         // headless plugins
-        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(*FPS)),
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(config.tick())),
         DiagnosticsPlugin,
         TerminalCtrlCHandlerPlugin,
         LogDiagnosticsPlugin::default(),
@@ -42,33 +72,47 @@ fn main() {
     #[cfg(feature = "gui")]
     app.add_plugins((DefaultPlugins, gui::GuiPlugin));
 
+    let (admin_tx, admin_rx) = admin::channel();
+    let bans = Bans::default();
+    net::backend_actix(&config, admin_tx, bans.clone());
+
     app
+        .insert_resource(WorldMin(config.world_min))
+        .insert_resource(config)
+        .insert_resource(admin_rx)
+        .insert_resource(bans)
         .add_plugins(ServerPlugins::default())
         .add_plugins(ProtocolPlugin)
         .add_plugins(OilRigPlugin)
         .add_plugins(WeaponPlugin)
+        .add_plugins(BoatPlugin)
+        .add_plugins(GridPlugin)
+        .add_plugins(SensorPlugin)
+        .add_plugins(NicknamePlugin)
+        .add_plugins(AdminPlugin)
+        .add_plugins(LeaderboardPlugin)
         .add_plugins(UpgradePlugin)
         .add_systems(Startup, setup)
         .add_plugins(WorldPlugin)
         // // handle client action
         .add_plugins(MovementPlugin { move_weapon: true })
-        .add_systems(FixedUpdate, recv_new_z_index)
+        .add_plugins(TurretPlugin)
+        .add_plugins(DivePlugin)
 
         // handle client req
         .add_observer(handle_new_client)
         .add_observer(handle_connected_client);
 
-    net::backend_actix();
-
     app.run();
 }
 
 static NEXT_CLIENT_ID: RwLock<u64> = RwLock::new(0);
 
 /// starts the server
-fn setup(mut commands: Commands) {
+fn setup(mut commands: Commands, config: Res<ServerConfig>) {
     let netcode_config = NetcodeConfig {
-        protocol_id: PROTOCOL_ID,
+        protocol_id: config.protocol_id,
+        private_key: config.private_key,
         num_disconnect_packets: 50,
         // client_timeout_secs: -1,
         ..Default::default()
@@ -77,12 +121,12 @@ fn setup(mut commands: Commands) {
     let server = commands
         .spawn((
             NetcodeServer::new(netcode_config),
-            LocalAddr(SERVER_ADDR),
+            LocalAddr(config.server_addr),
             WebTransportServerIo {
                 certificate: {
                     let runtime = tokio::runtime::Runtime::new().unwrap();
                     runtime.block_on(
-                        Identity::load_pemfiles("../cert/cert.pem", "../cert/key.pem")
+                        Identity::load_pemfiles(&config.cert, &config.key)
                     ).expect("Couldn't load certificate")
                 }
             },
@@ -105,11 +149,13 @@ fn handle_new_client(connecting_client: On<Add, LinkOf>, mut commands: Commands)
         .insert(ReplicationSender);
 }
 
-/// connected client. spawns the main boat entity
+/// connected client. sends the active definitions and spawns the main boat entity
 fn handle_connected_client(
     connected_client: On<Add, Connected>,
     clients: Query<&RemoteId, With<ClientOf>>,
     mut commands: Commands,
+    mut sender: ServerMultiMessageSender,
+    server: Single<&Server>,
 ) {
     let entity = connected_client.entity; // NOT equal to client id or Client entity in client's world
     let Ok(&RemoteId(client_id)) = clients.get(entity) else {
@@ -117,70 +163,18 @@ fn handle_connected_client(
         return;
     };
 
-
-    let boat = Boat::Yasen;
-    let position = vec2(
-        rand::random_range(-200.0..200.0),
-        rand::random_range(-200.0..200.0),
-    );
-
-    info!("Spawning ship");
-
-    let mut entity_commands = commands.spawn((
-        CustomTransform {
-            position: Position(position),
-            ..CustomTransform::default()
-        },
-        boat,
-        WeaponCounter::from_boat(&boat),
-        LastReloaded(boat.armanents().keys().map(|k| (*k, None)).collect()),
-        OCEAN_SURFACE,
-        PlayerStats::new(0),
-        
-        BoatClientId(client_id),
-        
-        Replicate::to_clients(NetworkTarget::All),
-        PredictionTarget::to_clients(NetworkTarget::Single(client_id)),
-        InterpolationTarget::to_clients(NetworkTarget::AllExceptSingle(client_id)),
-        
-        ActionState::<Rotate>::default(),
-        ActionState::<Move>::default(),
-        ActionState::<ZIndexUpdate>::default(),
-        
-        ControlledBy {
-            owner: entity,
-            lifetime: Lifetime::SessionBased
-        },
-    ));
-    entity_commands.insert(EntityOnServer(entity_commands.id().to_bits()));
-}
-
-fn recv_new_z_index(
-    // rxs: Query<&mut MessageReceiver<NewZIndex>>,
-    q: Query<(&ActionState<ZIndexUpdate>, &mut ZIndex)>
-) {
-    // for mut rx in rxs {
-        // for msg in rx.receive() {
-        //     let Ok(mut z_index) = z_index.get_mut(Entity::from_bits(msg.entity_on_server.0)) else {
-        //         error!("Client sent a non-existent Boat ID");
-        //         return;
-        //     };
-        //     info!(?msg.new_index);
-        //     *z_index = msg.new_index;
-        // }
-    // }
-    for (z_update, mut z_index) in q {
-        let Some(target) = z_update.0.0 else { continue; };
-        trace!("Updating ZIndex to {:?}", target);
-        *z_index = target;
+    if let Some(definitions) = definitions::active() {
+        sender.send::<_, SendToClientOrdered>(definitions, &server, &NetworkTarget::Single(client_id)).unwrap();
     }
+    boat::spawn_boat(&mut commands, boat::starting_boat(), client_id, entity);
 }
 
 /// gives the server a GUI for debugging
 #[cfg(feature = "gui")]
 mod gui {
     use super::*;
-    use common::primitives::Size;
+    use common::Boat;
+    use common::primitives::{CustomTransform, Size};
     pub struct GuiPlugin;
 
     impl Plugin for GuiPlugin {
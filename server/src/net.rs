@@ -1,25 +1,81 @@
 //! custom networking backend
+//!
+//! - `/join` hands out connect tokens
+//! - `/admin/...` lets ops moderate a live server, see [`admin`](crate::admin)
 
+use std::net::SocketAddr;
 use std::thread;
 
-use actix_web::{App, HttpServer, Responder, get};
-use common::TCP_ADDR;
+use bevy::log::{error, info};
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, Responder, get, http::header::{self, HeaderValue}, post, put, web};
+use common::GAME_ADDR_HEADER;
+use lightyear::netcode::{ConnectToken, Key};
+use lightyear::prelude::PeerId;
+use tokio::sync::oneshot;
 
-use crate::NEXT_CLIENT_ID;
+use crate::admin::{AdminCommand, AdminSender, Bans, RigRate};
+use crate::config::ServerConfig;
+use crate::{NEXT_CLIENT_ID, nickname};
 
-pub fn backend_actix() {
-    thread::spawn(|| {
+/// seconds a connect token can be used for, the nickname stays reserved as long
+pub(crate) const TOKEN_EXPIRE_SECS: i32 = 30;
+/// seconds without packets before either side drops the connection
+const TOKEN_TIMEOUT_SECS: i32 = 3;
+
+/// what `/join` needs from [`ServerConfig`]
+#[derive(Debug, Clone)]
+struct JoinSettings {
+    /// where clients connect, see [`ServerConfig::public_addr`]
+    public_addr: SocketAddr,
+    protocol_id: u64,
+    /// netcode rejects tokens signed with anything else
+    private_key: Key,
+    bans: Bans,
+}
+
+/// what `/admin/...` needs
+#[derive(Debug, Clone)]
+struct AdminSettings {
+    /// None if the admin API is disabled
+    token: Option<String>,
+    tx: AdminSender,
+}
+
+pub fn backend_actix(config: &ServerConfig, admin_tx: AdminSender, bans: Bans) {
+    let http_addr = config.http_addr;
+    let settings = JoinSettings {
+        public_addr: config.public_addr,
+        protocol_id: config.protocol_id,
+        private_key: config.private_key,
+        bans,
+    };
+    let admin = AdminSettings {
+        token: config.admin_token.clone(),
+        tx: admin_tx,
+    };
+    thread::spawn(move || {
         let rt = tokio::runtime::Builder::new_multi_thread()
             .worker_threads(2)
             .enable_all()
             .build()
             .unwrap();
         rt.block_on(async {
-            let server = HttpServer::new(|| {
+            let server = HttpServer::new(move || {
                 App::new()
-                    .service(return_aval_client_id)
+                    .app_data(web::Data::new(settings.clone()))
+                    .app_data(web::Data::new(admin.clone()))
+                    .service(join)
+                    .service(
+                        web::scope("/admin")
+                            .service(list_players)
+                            .service(kick)
+                            .service(ban)
+                            .service(announce)
+                            .service(set_rig_rate)
+                            .service(shutdown)
+                    )
             })
-            .bind(TCP_ADDR).unwrap()
+            .bind(http_addr).unwrap_or_else(|e| panic!("Couldn't bind {http_addr}: {e}"))
             .workers(2)
             .run();
             server.await.unwrap();
@@ -27,12 +83,161 @@ pub fn backend_actix() {
     });
 }
 
-#[get("/client_id")]
-async fn return_aval_client_id() -> impl Responder {
-    let mut guard = NEXT_CLIENT_ID.write().unwrap();
-    let ret = *guard;
-    *guard += 1;
-    ret.to_be_bytes().to_vec()
+/// the body is the nickname, responds with a connect token for a fresh client id
+/// and where to connect in [`GAME_ADDR_HEADER`], or a 400 with why the nickname was rejected
+///
+/// the id can't be chosen by the client, and netcode rejects expired or reused tokens
+#[post("/join")]
+async fn join(req: HttpRequest, body: String, settings: web::Data<JoinSettings>) -> impl Responder {
+    let mut response = join_response(&req, &body, &settings);
+    // the web client may be served from another origin, and has to read both the body and the game address
+    let headers = response.headers_mut();
+    headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, HeaderValue::from_static(GAME_ADDR_HEADER));
+    response
+}
+
+fn join_response(req: &HttpRequest, body: &str, settings: &JoinSettings) -> HttpResponse {
+    if req.peer_addr().is_some_and(|addr| settings.bans.is_banned(addr.ip())) {
+        return HttpResponse::Forbidden().body("Banned from this server");
+    }
+    let name = match nickname::validate(body) {
+        Ok(name) => name,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    let client_id = {
+        let mut guard = NEXT_CLIENT_ID.write().unwrap();
+        let ret = *guard;
+        *guard += 1;
+        ret
+    };
+    let token = ConnectToken::build(settings.public_addr, settings.protocol_id, client_id, settings.private_key)
+        .expire_seconds(TOKEN_EXPIRE_SECS)
+        .timeout_seconds(TOKEN_TIMEOUT_SECS)
+        .generate()
+        .and_then(|token| token.try_into_bytes());
+    let token = match token {
+        Ok(token) => token,
+        Err(e) => {
+            error!("Couldn't generate a connect token for client {client_id}: {e}");
+            return HttpResponse::InternalServerError().body("Couldn't generate a connect token");
+        }
+    };
+
+    let name = nickname::reserve(PeerId::Netcode(client_id), name);
+    info!("Client {client_id} joined as {name:?}");
+    HttpResponse::Ok()
+        .insert_header((GAME_ADDR_HEADER, settings.public_addr.to_string()))
+        .body(token.to_vec())
+}
+
+/// `Authorization: Bearer <admin_token>`, 404 if the admin API is disabled
+fn authorize(req: &HttpRequest, admin: &AdminSettings) -> Result<(), HttpResponse> {
+    let Some(expected) = &admin.token else {
+        return Err(HttpResponse::NotFound().finish());
+    };
+    let given = req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match given {
+        Some(given) if constant_time_eq(given.as_bytes(), expected.as_bytes()) => Ok(()),
+        _ => Err(HttpResponse::Unauthorized().finish()),
+    }
+}
+
+/// doesn't leak how much of the token was right through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// sends `command` to the world and waits for its answer, 503 if the world is gone (e.g. shutting down)
+async fn ask<T>(admin: &AdminSettings, command: impl FnOnce(oneshot::Sender<T>) -> AdminCommand) -> Result<T, HttpResponse> {
+    let (tx, rx) = oneshot::channel();
+    admin.tx.send(command(tx)).map_err(|_| HttpResponse::ServiceUnavailable().finish())?;
+    rx.await.map_err(|_| HttpResponse::ServiceUnavailable().finish())
+}
+
+/// JSON array of [`PlayerInfo`](crate::admin::PlayerInfo)
+#[get("/players")]
+async fn list_players(req: HttpRequest, admin: web::Data<AdminSettings>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &admin) {
+        return response;
+    }
+    match ask(&admin, AdminCommand::ListPlayers).await {
+        Ok(players) => HttpResponse::Ok().json(players),
+        Err(response) => response,
+    }
+}
+
+/// 404 if no such client is connected
+#[post("/players/{id}/kick")]
+async fn kick(req: HttpRequest, id: web::Path<u64>, admin: web::Data<AdminSettings>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &admin) {
+        return response;
+    }
+    let id = id.into_inner();
+    match ask(&admin, |reply| AdminCommand::Kick(id, reply)).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().body(format!("No client {id}")),
+        Err(response) => response,
+    }
+}
+
+/// kicks and bans the client's IP until the server restarts, 404 if no such client is connected
+#[post("/players/{id}/ban")]
+async fn ban(req: HttpRequest, id: web::Path<u64>, admin: web::Data<AdminSettings>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &admin) {
+        return response;
+    }
+    let id = id.into_inner();
+    match ask(&admin, |reply| AdminCommand::Ban(id, reply)).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().body(format!("No client {id}")),
+        Err(response) => response,
+    }
+}
+
+/// the body is shown to every player
+#[post("/announce")]
+async fn announce(req: HttpRequest, body: String, admin: web::Data<AdminSettings>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &admin) {
+        return response;
+    }
+    if body.trim().is_empty() {
+        return HttpResponse::BadRequest().body("Empty announcement");
+    }
+    match ask(&admin, |reply| AdminCommand::Announce(body, reply)).await {
+        Ok(Ok(())) => HttpResponse::NoContent().finish(),
+        Ok(Err(reason)) => HttpResponse::InternalServerError().body(reason),
+        Err(response) => response,
+    }
+}
+
+/// JSON [`RigRate`], 400 with why if any value is invalid
+#[put("/rig_rate")]
+async fn set_rig_rate(req: HttpRequest, rate: web::Json<RigRate>, admin: web::Data<AdminSettings>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &admin) {
+        return response;
+    }
+    let rate = rate.into_inner();
+    match ask(&admin, |reply| AdminCommand::SetRigRate(rate, reply)).await {
+        Ok(Ok(())) => HttpResponse::NoContent().finish(),
+        Ok(Err(reason)) => HttpResponse::BadRequest().body(reason),
+        Err(response) => response,
+    }
+}
+
+/// disconnects everyone, then the process exits once they're gone
+#[post("/shutdown")]
+async fn shutdown(req: HttpRequest, admin: web::Data<AdminSettings>) -> HttpResponse {
+    if let Err(response) = authorize(&req, &admin) {
+        return response;
+    }
+    match admin.tx.send(AdminCommand::Shutdown) {
+        Ok(()) => HttpResponse::Accepted().finish(),
+        Err(_) => HttpResponse::ServiceUnavailable().finish(),
+    }
 }
 /*
 /// receives data from a client
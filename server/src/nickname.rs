@@ -0,0 +1,170 @@
+//! player names, validated and reserved by `/join` in [`net`](crate::net) before the client connects,
+//! then attached to every boat of that client as [`Nickname`]
+//!
+//! reservations live in a static since the HTTP handlers run outside the bevy world
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::{LazyLock, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use bevy::prelude::*;
+use common::protocol::Nickname;
+use lightyear::prelude::{server::ClientOf, *};
+
+use crate::net;
+
+/// a reservation is dropped if its client hasn't connected by then, when its connect token expires
+const JOIN_TIMEOUT: Duration = Duration::from_secs(net::TOKEN_EXPIRE_SECS as u64);
+
+/// releases a client's name once it disconnects
+pub(crate) struct NicknamePlugin;
+
+impl Plugin for NicknamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(release_on_disconnect);
+    }
+}
+
+#[derive(Debug)]
+struct Reservation {
+    name: String,
+    /// None once the client connected
+    expires: Option<Instant>,
+}
+
+static RESERVED: LazyLock<RwLock<HashMap<PeerId, Reservation>>> = LazyLock::new(Default::default);
+/// lowercase words, see [`load_profanity`]
+static PROFANITY: OnceLock<Vec<String>> = OnceLock::new();
+
+/// why `/join` rejected a nickname, sent back to the client as is
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum NicknameError {
+    Empty,
+    TooLong,
+    InvalidChar(char),
+    Profane,
+}
+
+impl fmt::Display for NicknameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "Nickname can't be empty"),
+            Self::TooLong => write!(f, "Nickname can't be longer than {} characters", Nickname::MAX_LEN),
+            Self::InvalidChar(c) => write!(f, "Nickname can't contain {c:?}"),
+            Self::Profane => write!(f, "Nickname isn't allowed"),
+        }
+    }
+}
+
+/// one word per line, blank lines and lines starting with `#` are skipped
+///
+/// without a list, only length and charset are checked
+pub(crate) fn load_profanity(path: &Path) -> std::io::Result<usize> {
+    let words: Vec<String> = std::fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect();
+    let count = words.len();
+    PROFANITY.set(words).expect("Profanity list loaded twice");
+    Ok(count)
+}
+
+/// trims and collapses whitespace, then checks length, charset and the profanity list
+pub(crate) fn validate(raw: &str) -> Result<String, NicknameError> {
+    let name = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        return Err(NicknameError::Empty);
+    }
+    if name.chars().count() > Nickname::MAX_LEN {
+        return Err(NicknameError::TooLong);
+    }
+    if let Some(c) = name.chars().find(|&c| !Nickname::is_allowed_char(c)) {
+        return Err(NicknameError::InvalidChar(c));
+    }
+    // "b a d" or "b_a-d" shouldn't get around the list
+    let squashed: String = name.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_lowercase();
+    if PROFANITY.get().is_some_and(|words| words.iter().any(|word| squashed.contains(word.as_str()))) {
+        return Err(NicknameError::Profane);
+    }
+    Ok(name)
+}
+
+/// reserves `name` for `client_id`, suffixed with `#2`, `#3`... if someone else has it
+///
+/// `#` can't be typed, so a suffixed name never collides with a chosen one
+pub(crate) fn reserve(client_id: PeerId, name: String) -> String {
+    let mut reserved = RESERVED.write().unwrap();
+    let now = Instant::now();
+    reserved.retain(|_, reservation| reservation.expires.is_none_or(|expires| expires > now));
+
+    let taken = |candidate: &str| reserved.values().any(|reservation| reservation.name.eq_ignore_ascii_case(candidate));
+    let unique = if taken(&name) {
+        (2..).map(|n| format!("{name}#{n}")).find(|candidate| !taken(candidate)).unwrap()
+    } else {
+        name
+    };
+
+    reserved.insert(client_id, Reservation {
+        name: unique.clone(),
+        expires: Some(now + JOIN_TIMEOUT),
+    });
+    unique
+}
+
+/// the name reserved for `client_id`, keeping it until the client disconnects
+pub(crate) fn claim(client_id: PeerId) -> Option<Nickname> {
+    let mut reserved = RESERVED.write().unwrap();
+    let reservation = reserved.get_mut(&client_id)?;
+    reservation.expires = None;
+    Some(Nickname(reservation.name.clone()))
+}
+
+/// placeholder for clients that somehow connected without joining
+pub(crate) fn fallback(client_id: PeerId) -> Nickname {
+    Nickname(format!("Player {client_id}"))
+}
+
+fn release_on_disconnect(trigger: On<Remove, ClientOf>, clients: Query<&RemoteId>) {
+    let Ok(RemoteId(client_id)) = clients.get(trigger.entity) else {
+        return;
+    };
+    RESERVED.write().unwrap().remove(client_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(validate("  Sea \t  Wolf "), Ok("Sea Wolf".to_owned()));
+        assert_eq!(validate("under_score-1"), Ok("under_score-1".to_owned()));
+        assert_eq!(validate(" \t "), Err(NicknameError::Empty));
+
+        assert!(validate(&"a".repeat(Nickname::MAX_LEN)).is_ok());
+        assert_eq!(validate(&"a".repeat(Nickname::MAX_LEN + 1)), Err(NicknameError::TooLong));
+        // collapsed before counting
+        assert!(validate(&format!("{}    {}", "a".repeat(7), "b".repeat(8))).is_ok());
+
+        assert_eq!(validate("Captain#2"), Err(NicknameError::InvalidChar('#')));
+        assert_eq!(validate("Ünter"), Err(NicknameError::InvalidChar('Ü')));
+    }
+
+    #[test]
+    fn test_reserve() {
+        // RESERVED is shared with any other test, so ids and names are unlikely to be used elsewhere
+        let client = |n| PeerId::Netcode(u64::MAX - n);
+
+        assert_eq!(reserve(client(0), "Reserve Test".to_owned()), "Reserve Test");
+        assert_eq!(reserve(client(1), "Reserve Test".to_owned()), "Reserve Test#2");
+        // collisions ignore case
+        assert_eq!(reserve(client(2), "reserve TEST".to_owned()), "reserve TEST#3");
+
+        assert_eq!(claim(client(1)), Some(Nickname("Reserve Test#2".to_owned())));
+        assert_eq!(claim(client(3)), None);
+    }
+}
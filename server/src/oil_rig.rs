@@ -1,12 +1,10 @@
-use std::time::Duration;
-use std::{f32::consts::PI, ops::Range};
-use std::sync::LazyLock;
+use std::f32::consts::PI;
 use bevy::prelude::*;
 use lightyear::link::server::Server;
 use lightyear::prelude::{NetworkTarget, Replicate, ServerMultiMessageSender};
 use rand::{RngExt, rngs::ThreadRng, seq::IndexedRandom};
 
-use common::{Boat, OCEAN_SURFACE, UpgradeSet, eq};
+use common::{Boat, OCEAN_SURFACE, Sinking, UpgradeSet, eq};
 use common::collision::{out_of_bound_point, out_of_bounds, square_does_not_intersects};
 use common::primitives::{CustomTransform, Mk48Rect, PlayerStats, Point, Position, Radian, Speed, ZIndex, in_range};
 use common::protocol::{OilRigTransform as OilRig, PointTransform, SendToClient};
@@ -14,6 +12,10 @@ use common::util::{avaliable_cords, point_in_square};
 use common::WorldSize;
 
 use common::BoatClientId;
+use common::grid::SpatialGrid;
+
+use crate::config::ServerConfig;
+use crate::grid::GRID_SLACK;
 
 /// Replicated for OilRig entity:
 /// - [`OilRigInfo`]
@@ -27,7 +29,7 @@ pub struct OilRigPlugin;
 impl Plugin for OilRigPlugin {
     fn build(&self, app: &mut App) {
         app
-            .insert_resource(RigTimer::new_rand(&mut rand::rng()))
+            .add_systems(Startup, start_rig_timer)
             .add_systems(Update, spawn_rigs)
             .add_systems(
                 FixedUpdate,
@@ -42,15 +44,6 @@ impl Plugin for OilRigPlugin {
 
 // observed that a rig wasn't spawning points once
 
-/// use [`rand::rng()`] to determine whether to spawn a new [`Point`]
-static SPAWN_POINT_VEC: LazyLock<Vec<bool>> = LazyLock::new(|| {
-    #[cfg(debug_assertions)]
-    let false_vec = [false; 4];
-    #[cfg(not(debug_assertions))]
-    let false_vec = [false; 40];
-    false_vec.into_iter().chain([true]).collect::<Vec<bool>>()
-});
-
 /// the maximum radius around a rig which a point can spawn
 const SPAWN_POINT_RADIUS_MAX: f32 = 100.0;
 
@@ -61,6 +54,10 @@ const POINT_SPEED: Speed = Speed::from_knots(40.0);
 #[derive(Resource, Deref, DerefMut)]
 struct RigTimer(Timer);
 
+fn start_rig_timer(mut commands: Commands, config: Res<ServerConfig>) {
+    commands.insert_resource(RigTimer::new_rand(&mut rand::rng(), &config));
+}
+
 #[cfg(debug_assertions)]
 static mut DEBUG_SPAWN: bool = true;
 
@@ -71,7 +68,8 @@ fn spawn_rigs(
 
     mut commands: Commands,
     world_size: Res<WorldSize>,
-    spawned_rigs: Query<&PointTransform>
+    spawned_rigs: Query<&PointTransform>,
+    config: Res<ServerConfig>,
 ) {
     timer.tick(time.delta());
 
@@ -82,10 +80,11 @@ fn spawn_rigs(
             &mut commands,
             &mut rng,
             &world_size,
-            &spawned_rigs.iter().map(|i| i.position).collect::<Vec<Vec2>>()
+            &spawned_rigs.iter().map(|i| i.position).collect::<Vec<Vec2>>(),
+            &config,
         );
 
-        *timer = RigTimer::new_rand(&mut rng);
+        *timer = RigTimer::new_rand(&mut rng, &config);
     }
 
     if unsafe { DEBUG_SPAWN } {
@@ -94,7 +93,7 @@ fn spawn_rigs(
                 position: vec2(0.0, 0.0),
                 rotation: Radian::ZERO
             },
-            PointAmount::new(&mut rand::rng()),
+            PointAmount::new(&mut rand::rng(), &config),
             Replicate::to_clients(NetworkTarget::All)
         ));
 
@@ -110,6 +109,7 @@ fn spawn_rigs(
 /// ### Params
 /// - `world_size`: the [`Single<WorldSize>`]
 /// - `other_rigs`: all other rigs' centers
+/// - `config`: for [`ServerConfig::rig_points`]
 /// 
 /// ### Spawns
 /// - [`OilRigTransform`](OilRig) (consider changing name)
@@ -121,7 +121,8 @@ fn spawn_random_rig(
     commands: &mut Commands,
     rng: &mut ThreadRng,
     world_size: &WorldSize,
-    other_rigs: &[Vec2]
+    other_rigs: &[Vec2],
+    config: &ServerConfig,
 ) {
     let mut rotation;
     let mut center;
@@ -161,7 +162,7 @@ fn spawn_random_rig(
             position: center,
             rotation: Radian(rotation)
         },
-        PointAmount::new(rng),
+        PointAmount::new(rng, config),
         Replicate::to_clients(NetworkTarget::All)
     ));
 }
@@ -173,6 +174,7 @@ fn rig_spawn_points(
     mut commands: Commands,
     rigs: Query<(&mut PointAmount, &OilRig, Entity)>,
     world_size: Res<WorldSize>,
+    config: Res<ServerConfig>,
 ) {
     let mut rng = rand::rng();
 
@@ -181,7 +183,7 @@ fn rig_spawn_points(
             continue;
         }
 
-        if *SPAWN_POINT_VEC.choose(&mut rng).unwrap() {
+        if rng.random_bool(config.point_spawn_chance) {
             let cords = avaliable_cords(rig.position, OilRig::SPRITE_SIZE + SPAWN_POINT_RADIUS_MAX);
 
             let chosen_tile =  loop {
@@ -220,82 +222,73 @@ fn rig_spawn_points(
 /// 
 /// ignoring moving in the Z-axis
 /// 
-/// takes ~0.01 milliseconds to run once in a small map with 1 boat
+/// only boats in the [`SpatialGrid`] cells around each point are compared
 fn move_points(
     mut points_transform: Query<&mut PointTransform, With<Point>>,
-    boats: Query<(&CustomTransform, &Boat, &ZIndex)>,
+    boats: Query<(&CustomTransform, &Boat, &ZIndex), Without<Sinking>>,
+    boat_grid: Res<SpatialGrid<Boat>>,
 ) {
-    for (boats_in_range, mut point) in points_transform.iter_mut().filter_map(|point_info| {
-        let boats_in_range = boats.iter()
+    // the biggest circle hud a point can be in
+    let reach = boats.iter().map(|(_, boat, _)| boat.circle_hud_radius()).fold(0.0, f32::max) + GRID_SLACK;
+
+    for mut point in &mut points_transform {
+        let (point_position, point_depth) = (point.position, point.depth);
+        let closest = boat_grid.within(point_position, reach)
+            .filter_map(|(id, _)| boats.get(id).ok())
             .filter(|&(CustomTransform { position: Position(boat_pos), ..}, boat, boat_depth)| {
-                // info!(?boat_depth, ?point_info.depth);
-                in_range(*boat_pos, point_info.position, boat.circle_hud_radius())
+                in_range(*boat_pos, point_position, boat.circle_hud_radius())
                     // TODO points should "lock in" to a boat once it starts to dive
-                    && eq!(*boat_depth, point_info.depth, ?precision = PointTransform::PRECISION_TO_BOAT_Z)
+                    && eq!(*boat_depth, point_depth, ?precision = PointTransform::PRECISION_TO_BOAT_Z)
             })
             .map(|(CustomTransform { position, ..}, ..)| position.0)
-            .collect::<Vec<_>>();
+            .min_by(|a, b| a.distance_squared(point_position).total_cmp(&b.distance_squared(point_position)));
 
-        if boats_in_range.is_empty() {
-            None
-        } else {
-            Some((boats_in_range, point_info))
+        // move the point toward the closest player whose circle hud it's in
+        if let Some(boat_position) = closest {
+            point.position = point_position.move_towards(boat_position, POINT_SPEED.get_raw());  // TODO acceleration
         }
-    }) {
-        // move the point toward player for those in 1 player's circle hud
-        if boats_in_range.len() == 1 {
-            point.position = point.position.move_towards(
-                // safety: we know from the len check above
-                *unsafe { boats_in_range.get_unchecked(0) },
-                POINT_SPEED.get_raw(),
-            );
-            continue;
-        }
-
-        // safety: iterator won't run if boats empty
-        let boat_position = unsafe { boats_in_range.iter().min_by_key(|&boat_position| {
-            Vec2::distance_squared(point.position, *boat_position) as u32
-        }).unwrap_unchecked() };
-
-        point.position = point.position.move_towards(*boat_position, POINT_SPEED.get_raw());  // TODO acceleration
     }
 }
 
 /// increment player's score and despawning the Point if absorbed
+#[allow(clippy::too_many_arguments)]
 fn points_obsorbed_despawn(
     mut commands: Commands,
     points_transform: Query<(&PointTransform, &Point, &ParentRig, Entity)>,
-    mut boats: Query<(&CustomTransform, &ZIndex, &mut PlayerStats, &BoatClientId), With<Boat>>,
+    mut boats: Query<(&CustomTransform, &ZIndex, &mut PlayerStats, &BoatClientId), (With<Boat>, Without<Sinking>)>,
     mut point_amounts: Query<&mut PointAmount, With<OilRig>>,
+    boat_grid: Res<SpatialGrid<Boat>>,
 
     mut sender: ServerMultiMessageSender,
     server: Single<&Server>
 ) {
     for (point_transform, point, parent_rig, id) in points_transform.iter() {
-        if let Some((mut player_stats, client_id)) = boats
-            .iter_mut()
-            .find(|&(custom, z_index, ..)| {
-                // info!("Z-Index: {:?}", z_index);
-                eq!(custom.position.extend(*z_index), point_transform.to_actual_translation(), ?vec3, ?precision = PointTransform::PRECISION_TO_BOAT_Z)
-            })
-            .map(|(_, _, stats, client_id)| (stats, client_id))
-        {
-            commands.get_entity(id).unwrap().despawn();
-
-            player_stats.add_to_score(point.worth().into());
-            
-            // client spawns UI and collects user input
-            // TODO is this pointless? we're doing this to avoid checking display() every frame on client
-            trace!("Despawned a point");
-            sender.send::<_, SendToClient>(
-                &player_stats.display(),
-                &server,
-                &NetworkTarget::Single(client_id.0)
-            ).unwrap();
-
-            let mut point_amount = point_amounts.get_mut(parent_rig.0).unwrap();
-            point_amount.remove(point.worth());
-        }
+        let translation = point_transform.to_actual_translation();
+        let Some(absorbing) = boat_grid.within(point_transform.position, PointTransform::PRECISION_TO_BOAT_Z + GRID_SLACK)
+            .map(|(boat, _)| boat)
+            .find(|&boat| boats.get(boat).is_ok_and(|(custom, z_index, ..)| {
+                eq!(custom.position.extend(*z_index), translation, ?vec3, ?precision = PointTransform::PRECISION_TO_BOAT_Z)
+            }))
+        else {
+            continue;
+        };
+        let (_, _, mut player_stats, client_id) = boats.get_mut(absorbing).unwrap();
+
+        commands.get_entity(id).unwrap().despawn();
+
+        player_stats.add_to_score(point.worth().into());
+        
+        // client spawns UI and collects user input
+        // TODO is this pointless? we're doing this to avoid checking display() every frame on client
+        trace!("Despawned a point");
+        sender.send::<_, SendToClient>(
+            &player_stats.display(),
+            &server,
+            &NetworkTarget::Single(client_id.0)
+        ).unwrap();
+
+        let mut point_amount = point_amounts.get_mut(parent_rig.0).unwrap();
+        point_amount.remove(point.worth());
     }
 }
 
@@ -312,11 +305,9 @@ struct PointAmount {
 
 
 impl PointAmount {
-    /// maximum amount of points a rig can spawn
-    const SPAWN_POINT_AMOUNT_MAX: Range<u16> = 30..40;
-    /// generates a max point from default
-    fn new(rng: &mut ThreadRng) -> Self {
-        let max_point = rng.random_range(Self::SPAWN_POINT_AMOUNT_MAX);
+    /// generates a max point within [`ServerConfig::rig_points`]
+    fn new(rng: &mut ThreadRng, config: &ServerConfig) -> Self {
+        let max_point = rng.random_range(config.rig_points.clone());
 
         PointAmount {
             points: 0,
@@ -338,9 +329,8 @@ impl PointAmount {
 }
 
 impl RigTimer {
-    const DURATION_RANGE: Range<Duration> = Duration::from_secs(10)..Duration::from_secs(120);
-    /// random duration with [`TimerMode::Once`]
-    fn new_rand(rng: &mut ThreadRng) -> Self {
-        Self(Timer::new(rng.random_range(Self::DURATION_RANGE), TimerMode::Once))
+    /// random duration within [`ServerConfig::rig_interval`] with [`TimerMode::Once`]
+    fn new_rand(rng: &mut ThreadRng, config: &ServerConfig) -> Self {
+        Self(Timer::new(rng.random_range(config.rig_interval.clone()), TimerMode::Once))
     }
 }
\ No newline at end of file
@@ -0,0 +1,133 @@
+//! replicates boats, weapons and points only to the clients whose boat is near them
+//!
+//! boats and weapons have to be detected by the client's [`Sensors`], points only have to be within [`POINT_INTEREST_RANGE`].
+//! rigs are still replicated to everyone, they're few and never move
+//!
+//! candidates come from the [`SpatialGrid`]s, so the cost grows with how crowded an area is rather than the player count
+
+use bevy::ecs::entity::EntityHashSet;
+use bevy::prelude::*;
+use common::grid::SpatialGrid;
+use common::primitives::{CustomTransform, Point, ZIndex};
+use common::protocol::PingSonar;
+use common::{ACTIVE_SONAR_REVEAL_RANGE, Boat, Domain, MAX_SENSOR_RANGE, Sensors, Sinking, Weapon};
+use lightyear::prelude::{input::native::ActionState, server::ClientOf, *};
+
+/// pixels, points further from the client's boat aren't replicated to it
+const POINT_INTEREST_RANGE: f32 = 1200.0;
+
+/// keeps every boat's, weapon's and point's [`NetworkVisibility`] in sync with each client's [`Interest`]
+pub(crate) struct SensorPlugin;
+
+impl Plugin for SensorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(hide_by_default)
+            .add_observer(track_interest)
+            .add_systems(FixedUpdate, (toggle_active_sonar, update_visibility).chain());
+    }
+}
+
+/// everything currently replicated to the client, on the client entity (with [`ClientOf`])
+///
+/// kept so only the difference is sent to [`NetworkVisibility`] every tick
+#[derive(Debug, Component, Default)]
+struct Interest(EntityHashSet);
+
+fn track_interest(trigger: On<Add, ClientOf>, mut commands: Commands) {
+    commands.entity(trigger.entity).insert(Interest::default());
+}
+
+/// the boat is pinging with active sonar, server only
+///
+/// it sees further underwater, see [`Sensors::ping`], but anyone within [`ACTIVE_SONAR_REVEAL_RANGE`] sees it
+#[derive(Debug, Component)]
+struct Pinging;
+
+/// follows [`PingSonar`], sinking boats and boats without [`Boat::has_active_sonar`] stop pinging
+fn toggle_active_sonar(
+    boats: Query<(&ActionState<PingSonar>, &Boat, Has<Sinking>, Has<Pinging>, Entity)>,
+    mut commands: Commands,
+) {
+    for (input, boat, sinking, pinging, id) in boats {
+        let wants = input.0.0 && !sinking && boat.has_active_sonar();
+        if wants && !pinging {
+            debug!(?boat, "Active sonar on");
+            commands.entity(id).insert(Pinging);
+        } else if !wants && pinging {
+            commands.entity(id).remove::<Pinging>();
+        }
+    }
+}
+
+/// not sent to anyone until [`update_visibility`] finds who's interested
+fn hide_by_default(trigger: On<Add, (Boat, Weapon, Point)>, mut commands: Commands) {
+    commands.entity(trigger.entity).insert(NetworkVisibility::default());
+}
+
+/// a client always sees its own boat, other boats and weapons have to be within the [`Sensors`] range of its boat
+/// or close enough to a [`Pinging`] boat
+///
+/// sinking boats keep their sensors so the client can watch around until it respawns,
+/// a client without any boat sees nothing
+#[allow(clippy::too_many_arguments)]
+fn update_visibility(
+    eyes: Query<(&ControlledBy, &CustomTransform, &ZIndex, &Boat, Has<Pinging>, Entity)>,
+    pinging: Query<&CustomTransform, With<Pinging>>,
+    weapons: Query<(&Transform, &Weapon)>,
+    mut clients: Query<(&mut Interest, Entity), With<ClientOf>>,
+    mut visibilities: Query<&mut NetworkVisibility>,
+    boat_grid: Res<SpatialGrid<Boat>>,
+    weapon_grid: Res<SpatialGrid<Weapon>>,
+    point_grid: Res<SpatialGrid<Point>>,
+) {
+    for (controlled_by, custom, depth, boat, is_pinging, own) in &eyes {
+        let client = controlled_by.owner;
+        let Ok((mut interest, _)) = clients.get_mut(client) else {
+            continue;
+        };
+        let eye = custom.position.0;
+        let sensors = if is_pinging { boat.sensors(*depth).ping() } else { boat.sensors(*depth) };
+
+        let mut seen = EntityHashSet::default();
+        seen.insert(own);
+        let detected_boats = boat_grid.within(eye, MAX_SENSOR_RANGE).filter(|&(id, position)| {
+            eyes.get(id).is_ok_and(|(_, _, depth, ..)| sensors.detects(eye.distance(position), Domain::of_depth(*depth)))
+        });
+        seen.extend(detected_boats.map(|(id, _)| id));
+        seen.extend(boat_grid.within(eye, ACTIVE_SONAR_REVEAL_RANGE).map(|(id, _)| id).filter(|&id| pinging.contains(id)));
+
+        let detected_weapons = weapon_grid.within(eye, MAX_SENSOR_RANGE).filter(|&(id, position)| {
+            weapons.get(id).is_ok_and(|(transform, weapon)| {
+                let domain = weapon.weapon_type().domain().unwrap_or(Domain::of_depth(ZIndex(transform.translation.z)));
+                sensors.detects(eye.distance(position), domain)
+            })
+        });
+        seen.extend(detected_weapons.map(|(id, _)| id));
+        seen.extend(point_grid.within(eye, POINT_INTEREST_RANGE).map(|(id, _)| id));
+
+        for &id in seen.difference(&interest.0) {
+            if let Ok(mut visibility) = visibilities.get_mut(id) {
+                visibility.gain_visibility(client);
+            }
+        }
+        for &id in interest.0.difference(&seen) {
+            // may have been despawned since
+            if let Ok(mut visibility) = visibilities.get_mut(id) {
+                visibility.lose_visibility(client);
+            }
+        }
+        interest.0 = seen;
+    }
+
+    let watching: EntityHashSet = eyes.iter().map(|(controlled_by, ..)| controlled_by.owner).collect();
+    for (mut interest, client) in &mut clients {
+        if watching.contains(&client) {
+            continue;
+        }
+        for id in interest.0.drain() {
+            if let Ok(mut visibility) = visibilities.get_mut(id) {
+                visibility.lose_visibility(client);
+            }
+        }
+    }
+}
@@ -1,13 +1,21 @@
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
-use common::{Boat, BoatClientId, UpgradeEventCommonFinished, UpgradeSet, Weapon};
+use common::{
+    Ballistic, Boat, BoatClientId, DEPTH_CHARGE_BLAST_RADIUS, DEPTH_CHARGE_DEPTH_BAND, DEPTH_CHARGE_FUSE_RADIUS, Domain, LaunchedBy, Landed, OCEAN_FLOOR,
+    SHELL_SPLASH_RADIUS, Seeker, Sinking, Spoofed, Staged, Staging, SubKind, Travelled, Turrets, UpgradeEventCommonFinished, UpgradeSet, Weapon, WeaponType
+};
 use lightyear::prelude::*;
 use bevy::prelude::*;
-use common::primitives::{LastSpeed, Speed, TargetRotation, WeaponCounter};
-use common::protocol::{ReloadWeapon, SendToClient, SendToClientOrdered, SpawnWeapon, WeaponRollBack};
+use rand::RngExt;
+use common::collision::sat_collision;
+use common::primitives::{
+    CustomTransform, Health, LastSpeed, NormalizeRadian as _, Position, Radian, Size, Speed, TargetRotation, WeaponCounter, WrapRadian as _, ZIndex
+};
+use common::protocol::{DeathReason, EntityOnClient, ReloadWeapon, SendToClient, SendToClientOrdered, SpawnWeapon, WeaponImpact, WeaponRollBack, WeaponSpoofed};
 
-use crate::FPS;
+use crate::boat::Destroyed;
+use crate::config::ServerConfig;
 
 /// note that we're NOT using replication etc to sync weapon position and rotation due to small diff
 ///
@@ -24,54 +32,440 @@ impl Plugin for WeaponPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(FixedUpdate, recv_spawning.in_set(UpgradeSet::AfterRecvUpgrade))
             .add_observer(on_upgrade)
-            .add_systems(FixedUpdate, reload_weapons.in_set(UpgradeSet::AfterRecvUpgrade));
+            .add_observer(own_released_payload)
+            .add_observer(splash_shells)
+            .add_systems(FixedUpdate, reload_weapons.in_set(UpgradeSet::AfterRecvUpgrade))
+            .add_systems(FixedUpdate, (spoof_torpedoes, intercept, detect_hits, detonate_depth_charges).chain());
     }
 }
 
+/// who fired the weapon, server only
+#[derive(Debug, Component, Clone, Copy)]
+pub(crate) struct WeaponOwner {
+    /// the firing boat, which the weapon can't hit
+    pub boat: Entity,
+    pub boat_type: Boat,
+    pub client_id: PeerId,
+    /// the owner's local copy of the weapon
+    pub entity_on_client: EntityOnClient,
+}
+
+/// sonar decoys a torpedo already rolled against, server only
+#[derive(Debug, Component, Default)]
+struct ResistedDecoys(Vec<Entity>);
+
 /// spawns server's independent copy of Weapon, locally moved if validation passes
 /// 
+/// the firing boat is the one controlled by the client the message came from, and where the weapon
+/// leaves from is recomputed from our own state, see [`launch`]
+///
 /// we're taking 1 away from selected from weaponcounter here, [`WeaponCounter::selected`] is ignored on server
 fn recv_spawning(
-    rxs: Query<&mut MessageReceiver<SpawnWeapon>>,
+    rxs: Query<(&mut MessageReceiver<SpawnWeapon>, &RemoteId, Entity), With<ClientOf>>,
     mut commands: Commands,
-    mut boat_q: Query<(&BoatClientId, &mut WeaponCounter, &mut LastReloaded)>,
+    mut boat_q: Query<(&ControlledBy, &mut WeaponCounter, &mut LastReloaded, &Boat, &CustomTransform, &ZIndex, &Turrets, Entity), Without<Sinking>>,
     mut sender: ServerMultiMessageSender,
     server: Single<&Server>,
 ) {
-    for mut rx in rxs {
+    for (mut rx, &RemoteId(client_id), client) in rxs {
         for msg in rx.receive() {
             debug!("New weapon {:?}", msg.weapon);
-            let (_, mut counter, mut reload_map) = boat_q.iter_mut().find(|(c, ..)| c.0 == msg.client_id).unwrap();
-            let Some(count) =  counter.weapons.get_mut(&msg.weapon) else { panic!("{:?}", counter) };
-            if count.avaliable == 0 {
-                sender.send::<_, SendToClient>(&WeaponRollBack::Despawn { entity: msg.entity_on_client }, &server, &NetworkTarget::Single(msg.client_id)).unwrap();
-                info!("Client sent a weapon request but they don't have enough weapons. Should be caught");
+            let Some((_, mut counter, mut reload_map, &boat_type, custom, &depth, turrets, boat)) = boat_q.iter_mut().find(|(controlled_by, ..)| controlled_by.owner == client) else {
+                sender.send::<_, SendToClient>(&WeaponRollBack::Despawn { entity: msg.entity_on_client }, &server, &NetworkTarget::Single(client_id)).unwrap();
+                debug!("Client fired from a sinking boat");
+                continue;
+            };
+            let Some((position, starting_rotation, end_rotation)) = launch(&msg, boat_type, custom, depth, turrets) else {
+                sender.send::<_, SendToClient>(&WeaponRollBack::Despawn { entity: msg.entity_on_client }, &server, &NetworkTarget::Single(client_id)).unwrap();
+                info!("Client {client_id} fired {:?} from an invalid mount {:?}", msg.weapon, msg.mount);
+                continue;
+            };
+            if let Err(reason) = take_weapon(&mut counter, &mut reload_map, msg.weapon) {
+                sender.send::<_, SendToClient>(&WeaponRollBack::Despawn { entity: msg.entity_on_client }, &server, &NetworkTarget::Single(client_id)).unwrap();
+                info!("Client {client_id} fired {:?} but {reason}", msg.weapon);
                 continue;
             }
 
-            let reload = reload_map.get_mut(&msg.weapon).unwrap();
-            if reload.is_none() {  // 1st: full ammo, 2nd: upgraded
-                *reload = Some(Instant::now());  // set latest "reload" time at fire time
+            // rolled here so the client can't pick its own dispersion
+            let ballistic = Ballistic::new(msg.weapon, position.xy(), msg.target, end_rotation, Some(rand::random()));
+            let heading = ballistic.map_or(end_rotation, |(_, heading)| heading);
+
+            // the client's boat and turrets are predicted, only correct it if it's noticeably off.
+            // shells are always corrected, the client doesn't know the dispersion
+            let off = |ours: Radian, theirs: Radian| (ours - theirs).normalize().abs() > LAUNCH_ROTATION_TOLERANCE;
+            if ballistic.is_some()
+                || position.xy().distance(msg.position.xy()) > LAUNCH_POSITION_TOLERANCE
+                || off(starting_rotation, msg.starting_rotation)
+                || off(end_rotation, msg.end_rotation)
+            {
+                debug!("Correcting {:?} fired from mount {:?}", msg.weapon, msg.mount);
+                sender.send::<_, SendToClient>(
+                    &WeaponRollBack::Transform {
+                        position,
+                        rotation: starting_rotation,
+                        end_rotation: heading,
+                        ballistic: ballistic.map(|(ballistic, _)| ballistic),
+                        entity: msg.entity_on_client,
+                    },
+                    &server,
+                    &NetworkTarget::Single(client_id)
+                ).unwrap();
             }
-            count.avaliable -= 1;
 
-            commands.spawn((
+            let mut weapon = commands.spawn((
                 // transform replicated to other clients
                 Transform {
-                    translation: msg.position,
-                    // follows boat rotation
-                    rotation: msg.starting_rotation.to_quat(),
+                    translation: position,
+                    // follows the mount or the boat
+                    rotation: starting_rotation.to_quat(),
                     ..default()
                 },
                 msg.weapon,
 
-                TargetRotation(msg.end_rotation),
+                TargetRotation(heading),
                 LastSpeed(Speed::ZERO),
+                LaunchedBy(boat),
+                WeaponOwner {
+                    boat,
+                    boat_type,
+                    client_id,
+                    entity_on_client: msg.entity_on_client,
+                },
 
                 // replicated to all but the controlling client, controlling client
                 // simulates weapon locally without server intervention
-                Replicate::to_clients(NetworkTarget::AllExceptSingle(msg.client_id))
+                Replicate::to_clients(NetworkTarget::AllExceptSingle(client_id))
             ));
+            if let Some(seeker) = Seeker::new(msg.weapon) {
+                weapon.insert(seeker);
+            }
+            if let Some(staging) = Staging::new(msg.weapon, position.xy(), msg.target) {
+                weapon.insert(staging);
+            }
+            if let Some((ballistic, _)) = ballistic {
+                weapon.insert(ballistic);
+            }
+        }
+    }
+}
+
+/// takes one `weapon` from the boat, starting its reload if it was full
+///
+/// the weapon comes from the client, so it may not even be one the boat carries
+fn take_weapon(counter: &mut WeaponCounter, reload_map: &mut LastReloaded, weapon: Weapon) -> Result<(), &'static str> {
+    let (Some(count), Some(reload)) = (counter.weapons.get_mut(&weapon), reload_map.get_mut(&weapon)) else {
+        return Err("the boat doesn't carry it");
+    };
+    if count.avaliable == 0 {
+        return Err("none are left, should be caught by the client");
+    }
+    if reload.is_none() {  // 1st: full ammo, 2nd: upgraded
+        *reload = Some(Instant::now());  // set latest "reload" time at fire time
+    }
+    count.avaliable -= 1;
+    Ok(())
+}
+
+/// pixels the client's launch position may be off from ours before its weapon is corrected
+const LAUNCH_POSITION_TOLERANCE: f32 = 10.0;
+/// how far the client's launch rotations may be off from ours before its weapon is corrected
+const LAUNCH_ROTATION_TOLERANCE: Radian = Radian::from_deg(5.0);
+
+/// (position, starting rotation, end rotation) from our own [`CustomTransform`] and [`Turrets`], None if the request is invalid
+///
+/// weapons the boat has mounts for must leave from one, and a turret fires where it points
+/// so its shells can't leave outside the arc. the client's position and rotations are ignored
+fn launch(msg: &SpawnWeapon, boat: Boat, custom: &CustomTransform, depth: ZIndex, turrets: &Turrets) -> Option<(Vec3, Radian, Radian)> {
+    if !msg.end_rotation.0.is_finite() || !msg.target.is_finite() {
+        return None;
+    }
+    let weapon_type = msg.weapon.weapon_type();
+    let Some(index) = msg.mount.map(usize::from) else {
+        if boat.mounts().iter().any(|mount| mount.weapon_type == weapon_type) {
+            return None;
+        }
+        let position = if weapon_type == WeaponType::DepthCharge {
+            boat.stern(custom.position.0, custom.rotation)
+        } else {
+            custom.position.0
+        };
+        return Some((position.extend(*depth), custom.rotation, msg.end_rotation.normalize()));
+    };
+    let mount = boat.mounts().get(index).filter(|mount| mount.weapon_type == weapon_type)?;
+    let azimuth = *turrets.0.get(index)?;
+    if !mount.allows(azimuth) {
+        return None;
+    }
+
+    let position = mount.world_position(custom).extend(*depth);
+    let starting_rotation = turrets.world_azimuth(index, custom);
+    // fixed mounts let the weapon turn towards the cursor after launch
+    let end_rotation = if mount.is_fixed() { msg.end_rotation.normalize() } else { starting_rotation };
+    Some((position, starting_rotation, end_rotation))
+}
+
+/// checks every weapon against every boat, damaging the boat and despawning the weapon on impact
+///
+/// both are swept by their speed in [`sat_collision`] so fast shells don't tunnel through boats
+fn detect_hits(
+    mut commands: Commands,
+    weapons: Query<(&Transform, &Weapon, &LastSpeed, &WeaponOwner, Entity)>,
+    mut boats: Query<(&CustomTransform, &Boat, &ZIndex, &mut Health, Entity), Without<Sinking>>,
+    mut sender: ServerMultiMessageSender,
+    server: Single<&Server>,
+) {
+    for (transform, weapon, last_speed, owner, weapon_id) in weapons {
+        let weapon_type = weapon.weapon_type();
+        if !weapon_type.hits_on_contact() {
+            continue;
+        }
+        let weapon_transform = CustomTransform {
+            speed: last_speed.0,
+            position: Position(transform.translation.xy()),
+            rotation: transform.rotation.wrap_radian(),
+        };
+        let weapon_size = weapon.render_size();
+
+        let Some((boat, mut health, boat_id)) = boats.iter_mut()
+            // the boat may have sunk earlier this tick, Sinking is inserted by commands
+            .filter(|(_, _, _, health, id)| *id != owner.boat && !health.is_dead())
+            // missiles and rockets fly over submerged boats
+            .filter(|(_, _, depth, ..)| weapon_type.strikes(Domain::of_depth(**depth)))
+            .find(|(custom, boat, ..)| sat_collision(
+                weapon_transform,
+                weapon_size,
+                weapon_size.x / 2.0,
+                **custom,
+                boat.render_size(),
+                boat.radius(),
+            ))
+            .map(|(_, boat, _, health, id)| (boat, health, id))
+        else {
+            continue;
+        };
+
+        let dead = health.damage(weapon.damage());
+        debug!("{weapon:?} hit {boat:?} ({boat_id}), health left: {}", health.current());
+        if dead {
+            commands.trigger(Destroyed {
+                entity: boat_id,
+                reason: DeathReason::Weapon { weapon: *weapon, killer: owner.boat_type },
+            });
+        }
+
+        commands.entity(weapon_id).despawn();
+        sender.send::<_, SendToClient>(
+            &WeaponImpact { entity: owner.entity_on_client },
+            &server,
+            &NetworkTarget::Single(owner.client_id)
+        ).unwrap();
+    }
+}
+
+/// sets off depth charges near a submarine or on the ocean floor, damaging every submarine in the blast
+fn detonate_depth_charges(
+    mut commands: Commands,
+    weapons: Query<(&Transform, &Weapon, &WeaponOwner, Entity)>,
+    mut boats: Query<(&CustomTransform, &Boat, &ZIndex, &mut Health, Entity), Without<Sinking>>,
+    mut sender: ServerMultiMessageSender,
+    server: Single<&Server>,
+) {
+    for (transform, weapon, owner, weapon_id) in weapons {
+        if weapon.weapon_type() != WeaponType::DepthCharge {
+            continue;
+        }
+        let position = transform.translation.xy();
+        let depth = transform.translation.z;
+        // (horizontal distance) if the submarine is within the depth band
+        let in_band = |custom: &CustomTransform, boat: &Boat, z: &ZIndex| {
+            (boat.sub_kind() == SubKind::Submarine && (z.0 - depth).abs() <= DEPTH_CHARGE_DEPTH_BAND)
+                .then(|| custom.position.0.distance(position))
+        };
+
+        let fused = boats.iter()
+            .filter_map(|(custom, boat, z, ..)| in_band(custom, boat, z))
+            .any(|distance| distance <= DEPTH_CHARGE_FUSE_RADIUS);
+        if !fused && depth > OCEAN_FLOOR.0 {
+            continue;
+        }
+
+        for (custom, boat, z, mut health, boat_id) in &mut boats {
+            let Some(distance) = in_band(custom, boat, z).filter(|d| *d < DEPTH_CHARGE_BLAST_RADIUS) else {
+                continue;
+            };
+            // the boat may have sunk earlier this tick, Sinking is inserted by commands
+            if health.is_dead() {
+                continue;
+            }
+            let damage = weapon.damage() * (1.0 - distance / DEPTH_CHARGE_BLAST_RADIUS);
+            debug!("{weapon:?} detonated {distance} pixels from {boat:?} ({boat_id})");
+            if health.damage(damage) {
+                commands.trigger(Destroyed {
+                    entity: boat_id,
+                    reason: DeathReason::Weapon { weapon: *weapon, killer: owner.boat_type },
+                });
+            }
+        }
+
+        commands.entity(weapon_id).despawn();
+        sender.send::<_, SendToClient>(
+            &WeaponImpact { entity: owner.entity_on_client },
+            &server,
+            &NetworkTarget::Single(owner.client_id)
+        ).unwrap();
+    }
+}
+
+/// the payload belongs to the stage's owner, who simulates its own copy
+///
+/// keeps the stage's [`EntityOnClient`] so the owner can still be told about impacts
+fn own_released_payload(
+    trigger: On<Staged>,
+    owners: Query<&WeaponOwner>,
+    mut commands: Commands,
+) {
+    let Ok(owner) = owners.get(trigger.stage) else {
+        return;
+    };
+    commands.entity(trigger.entity).insert((
+        *owner,
+        Replicate::to_clients(NetworkTarget::AllExceptSingle(owner.client_id)),
+    ));
+}
+
+/// rolls [`ServerConfig::spoof_chance`] once per decoy for torpedoes that see an emitting decoy and a real target at once
+///
+/// a lured torpedo gets [`Spoofed`], replicated to other clients and sent to its owner
+fn spoof_torpedoes(
+    mut commands: Commands,
+    mut torpedoes: Query<(&Transform, &Weapon, &Seeker, &WeaponOwner, Option<&mut ResistedDecoys>, Entity), Without<Spoofed>>,
+    decoys: Query<(&Transform, &Weapon, &Travelled, &WeaponOwner, Entity)>,
+    config: Res<ServerConfig>,
+    mut sender: ServerMultiMessageSender,
+    server: Single<&Server>,
+) {
+    let mut rng = rand::rng();
+
+    for (transform, weapon, seeker, owner, mut resisted, torpedo_id) in &mut torpedoes {
+        let weapon_type = weapon.weapon_type();
+        // a locked target is always inside the cone
+        if weapon_type != WeaponType::Torpedo || seeker.target().is_none() {
+            continue;
+        }
+        let seen = decoys.iter()
+            .filter(|(_, decoy, travelled, decoy_owner, id)| {
+                decoy.weapon_type() == WeaponType::SonarDecoy
+                    && travelled.decoy_emitting()
+                    && decoy_owner.boat != owner.boat
+                    && !resisted.as_ref().is_some_and(|r| r.0.contains(id))
+            })
+            .find(|(decoy_transform, ..)| Seeker::sight(weapon_type, transform, decoy_transform.translation.xy()).is_some());
+        let Some((.., decoy_id)) = seen else {
+            continue;
+        };
+
+        if rng.random_bool(config.spoof_chance) {
+            debug!("{weapon:?} ({torpedo_id}) lured by decoy {decoy_id}");
+            commands.entity(torpedo_id).insert(Spoofed(decoy_id));
+            sender.send::<_, SendToClient>(
+                &WeaponSpoofed { entity: owner.entity_on_client, decoy: decoy_id },
+                &server,
+                &NetworkTarget::Single(owner.client_id)
+            ).unwrap();
+        } else if let Some(resisted) = resisted.as_mut() {
+            resisted.0.push(decoy_id);
+        } else {
+            commands.entity(torpedo_id).insert(ResistedDecoys(vec![decoy_id]));
+        }
+    }
+}
+
+/// destroys missiles and rockets hit by another boat's anti-air, along with the interceptor
+///
+/// runs before [`detect_hits`] so an intercepted missile can't also hit its target this tick
+fn intercept(
+    mut commands: Commands,
+    weapons: Query<(&Transform, &Weapon, &LastSpeed, &WeaponOwner, Entity)>,
+    mut sender: ServerMultiMessageSender,
+    server: Single<&Server>,
+) {
+    let swept = |transform: &Transform, last_speed: &LastSpeed| CustomTransform {
+        speed: last_speed.0,
+        position: Position(transform.translation.xy()),
+        rotation: transform.rotation.wrap_radian(),
+    };
+    // each weapon is destroyed at most once
+    let mut destroyed = Vec::new();
+
+    for (transform, weapon, last_speed, owner, interceptor_id) in &weapons {
+        if weapon.weapon_type() != WeaponType::AntiAir {
+            continue;
+        }
+        let interceptor_size = weapon.render_size();
+
+        let Some((target, target_owner, target_id)) = weapons.iter()
+            .filter(|(_, other, _, other_owner, id)| {
+                other.weapon_type().domain() == Some(Domain::Air)
+                    && other_owner.boat != owner.boat
+                    && !destroyed.contains(id)
+            })
+            .find(|(other_transform, other, other_speed, ..)| {
+                let other_size = other.render_size();
+                sat_collision(
+                    swept(transform, last_speed),
+                    interceptor_size,
+                    interceptor_size.x / 2.0,
+                    swept(other_transform, other_speed),
+                    other_size,
+                    other_size.x / 2.0,
+                )
+            })
+            .map(|(_, other, _, other_owner, id)| (other, other_owner, id))
+        else {
+            continue;
+        };
+
+        debug!("{weapon:?} intercepted {target:?} ({target_id})");
+        destroyed.extend([interceptor_id, target_id]);
+        // replicated weapons despawn on the other clients, the owners simulate theirs locally
+        for (id, owner) in [(interceptor_id, owner), (target_id, target_owner)] {
+            commands.entity(id).despawn();
+            sender.send::<_, SendToClient>(
+                &WeaponImpact { entity: owner.entity_on_client },
+                &server,
+                &NetworkTarget::Single(owner.client_id)
+            ).unwrap();
+        }
+    }
+}
+
+/// damages surface boats near where a [`Ballistic`] shell landed
+fn splash_shells(
+    trigger: On<Landed>,
+    mut commands: Commands,
+    shells: Query<(&Weapon, &WeaponOwner)>,
+    mut boats: Query<(&CustomTransform, &Boat, &ZIndex, &mut Health, Entity), Without<Sinking>>,
+) {
+    let Ok((weapon, owner)) = shells.get(trigger.entity) else {
+        return;
+    };
+
+    for (custom, boat, z, mut health, boat_id) in &mut boats {
+        // the boat may have sunk earlier this tick, Sinking is inserted by commands
+        if boat_id == owner.boat || Domain::of_depth(*z) != Domain::Surface || health.is_dead() {
+            continue;
+        }
+        let distance = boat.keel_distance(custom.position.0, custom.rotation, trigger.position);
+        if distance >= SHELL_SPLASH_RADIUS {
+            continue;
+        }
+        let damage = weapon.damage() * (1.0 - distance / SHELL_SPLASH_RADIUS);
+        debug!("{weapon:?} landed {distance} pixels from {boat:?} ({boat_id})");
+        if health.damage(damage) {
+            commands.trigger(Destroyed {
+                entity: boat_id,
+                reason: DeathReason::Weapon { weapon: *weapon, killer: owner.boat_type },
+            });
         }
     }
 }
@@ -129,4 +523,25 @@ fn reload_weapons(
 
 // reloading mechanisms:
 //      - reload starts when a slot is non empty, filling the timer
-//      - 
\ No newline at end of file
+//      - 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_weapon() {
+        let boat = Boat::Yasen;
+        let mut counter = WeaponCounter::from_boat(&boat);
+        let mut reload_map = LastReloaded(boat.armanents().keys().map(|k| (*k, None)).collect());
+
+        // the client can send any weapon, Yasen doesn't carry Mark18
+        assert!(take_weapon(&mut counter, &mut reload_map, Weapon::Mark18).is_err());
+
+        assert!(take_weapon(&mut counter, &mut reload_map, Weapon::Igla).is_ok());
+        assert!(reload_map[&Weapon::Igla].is_some());
+        assert!(take_weapon(&mut counter, &mut reload_map, Weapon::Igla).is_ok());
+        assert!(take_weapon(&mut counter, &mut reload_map, Weapon::Igla).is_err());
+        assert_eq!(counter.weapons.get(&Weapon::Igla).unwrap().avaliable, 0);
+    }
+}
\ No newline at end of file